@@ -0,0 +1,165 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use d_heap::heap::Heap;
+use d_heap::priority::MaxOrder;
+use d_heap::rng::Rng;
+use std::collections::BinaryHeap;
+
+/// Branching factors compared against std's binary heap.
+const DS: [u32; 4] = [2, 4, 8, 16];
+
+/// Element counts per workload.
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn values(size: usize) -> Vec<i32> {
+    let mut rng = Rng::new(size as u64);
+    (0..size)
+        .map(|_| rng.range_i32(i32::MIN, i32::MAX))
+        .collect()
+}
+
+fn empty_heap(d: u32) -> Heap {
+    Heap::with_priority(d, &[], MaxOrder).unwrap()
+}
+
+/// Builds a heap from an unordered slice in one go.
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for size in SIZES {
+        let items = values(size);
+        for d in DS {
+            group.bench_with_input(
+                BenchmarkId::new(format!("d={}", d), size),
+                &items,
+                |b, items| b.iter(|| Heap::new(d, black_box(items)).unwrap()),
+            );
+        }
+        group.bench_with_input(BenchmarkId::new("BinaryHeap", size), &items, |b, items| {
+            b.iter(|| BinaryHeap::from(black_box(items).clone()))
+        });
+    }
+    group.finish();
+}
+
+/// Inserts every value one at a time into an empty heap.
+fn bench_push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push");
+    for size in SIZES {
+        let items = values(size);
+        for d in DS {
+            group.bench_with_input(
+                BenchmarkId::new(format!("d={}", d), size),
+                &items,
+                |b, items| {
+                    b.iter(|| {
+                        let mut heap = empty_heap(d);
+                        for &x in items {
+                            heap.insert(x).unwrap();
+                        }
+                        heap
+                    })
+                },
+            );
+        }
+        group.bench_with_input(BenchmarkId::new("BinaryHeap", size), &items, |b, items| {
+            b.iter(|| {
+                let mut heap = BinaryHeap::new();
+                for &x in items {
+                    heap.push(x);
+                }
+                heap
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Drains a full heap, excluding the time to build it.
+fn bench_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pop");
+    for size in SIZES {
+        let items = values(size);
+        for d in DS {
+            let heap = Heap::new(d, &items).unwrap();
+            group.bench_with_input(
+                BenchmarkId::new(format!("d={}", d), size),
+                &heap,
+                |b, heap| {
+                    b.iter_batched(
+                        || heap.clone(),
+                        |mut heap| {
+                            while let Ok(x) = heap.extract_max() {
+                                black_box(x);
+                            }
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+        let heap = BinaryHeap::from(items);
+        group.bench_with_input(BenchmarkId::new("BinaryHeap", size), &heap, |b, heap| {
+            b.iter_batched(
+                || heap.clone(),
+                |mut heap| {
+                    while let Some(x) = heap.pop() {
+                        black_box(x);
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Starts from a heap of half the values, then alternates two inserts with
+/// one extraction, the steady churn of a scheduler queue.
+fn bench_mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mixed");
+    for size in SIZES {
+        let items = values(size);
+        let (start, rest) = items.split_at(size / 2);
+        for d in DS {
+            let heap = Heap::new(d, start).unwrap();
+            group.bench_with_input(
+                BenchmarkId::new(format!("d={}", d), size),
+                &heap,
+                |b, heap| {
+                    b.iter_batched(
+                        || heap.clone(),
+                        |mut heap| {
+                            for pair in rest.chunks(2) {
+                                for &x in pair {
+                                    heap.insert(x).unwrap();
+                                }
+                                black_box(heap.extract_max().unwrap());
+                            }
+                            heap
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+        let heap = BinaryHeap::from(start.to_vec());
+        group.bench_with_input(BenchmarkId::new("BinaryHeap", size), &heap, |b, heap| {
+            b.iter_batched(
+                || heap.clone(),
+                |mut heap| {
+                    for pair in rest.chunks(2) {
+                        for &x in pair {
+                            heap.push(x);
+                        }
+                        black_box(heap.pop().unwrap());
+                    }
+                    heap
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_build, bench_push, bench_pop, bench_mixed);
+criterion_main!(benches);
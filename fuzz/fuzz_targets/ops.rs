@@ -0,0 +1,100 @@
+#![no_main]
+
+//! Reads the input as a sequence of heap operations, applies each to a
+//! bounded Heap and to the reference Model through `model::check_op`, and
+//! fails on the first disagreement.
+//!
+//! The first byte picks d. After that every operation starts with a tag
+//! byte, followed by its operands:
+//!
+//! * insert: four bytes, a little-endian i32
+//! * extract_max: nothing
+//! * change_d: one byte for the new d
+//! * increase_key, decrease_key: a target byte, then two bytes for the delta
+//! * delete: a target byte
+//!
+//! A target byte below 128 picks a live handle; from 128 up it picks one
+//! whose element already left the heap, so stale handles get coverage too.
+//! Input that runs out mid-operation ends the run.
+
+use d_heap::handles::Handle;
+use d_heap::heap::{Heap, HEAP_MAX_SIZE};
+use d_heap::model::{self, Model, Op};
+use d_heap::priority::MaxOrder;
+use libfuzzer_sys::fuzz_target;
+
+struct Input<'a> {
+    bytes: &'a [u8],
+}
+
+impl Input<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.bytes.split_first_chunk::<N>()?;
+        self.bytes = rest;
+        Some(*head)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take::<1>().map(|[b]| b)
+    }
+}
+
+/// Picks the handle a target byte names, or None if there is nothing to pick.
+fn target(heap: &Heap, retired: &[Handle], pick: u8) -> Option<Handle> {
+    let pick = pick as usize;
+    if pick < 128 {
+        (!heap.is_empty()).then(|| heap.handle_at(pick % heap.len()).ok())?
+    } else {
+        (!retired.is_empty()).then(|| retired[(pick - 128) % retired.len()])
+    }
+}
+
+/// Decodes the next operation, or None once the input runs out.
+fn next_op(input: &mut Input, heap: &Heap, retired: &[Handle]) -> Option<Op> {
+    let op = match input.byte()? % 6 {
+        0 => Op::Insert(i32::from_le_bytes(input.take()?)),
+        1 => Op::ExtractMax,
+        2 => Op::ChangeD(1 + (input.byte()? % 10) as u32),
+        tag @ (3 | 4) => {
+            let pick = input.byte()?;
+            let delta = i16::from_le_bytes(input.take()?) as i32;
+            let Some(handle) = target(heap, retired, pick) else {
+                return Some(Op::ExtractMax);
+            };
+            let value = heap.get(handle).copied().unwrap_or(0);
+            if tag == 3 {
+                Op::IncreaseKey(handle, value.saturating_add(delta))
+            } else {
+                Op::DecreaseKey(handle, value.saturating_sub(delta))
+            }
+        }
+        _ => match target(heap, retired, input.byte()?) {
+            Some(handle) => Op::Delete(handle),
+            None => Op::ExtractMax,
+        },
+    };
+    Some(op)
+}
+
+fuzz_target!(|bytes: &[u8]| {
+    let mut input = Input { bytes };
+    let Some(d) = input.byte() else {
+        return;
+    };
+    let mut heap = Heap::bounded(1 + (d % 10) as u32, HEAP_MAX_SIZE, &[], MaxOrder).unwrap();
+    let mut model = Model::new();
+    let mut retired = Vec::new();
+
+    while let Some(op) = next_op(&mut input, &heap, &retired) {
+        let leaving = match op {
+            Op::ExtractMax => heap.handle_at(0).ok(),
+            Op::Delete(handle) => heap.get(handle).is_ok().then_some(handle),
+            _ => None,
+        };
+        if let Err(reason) = model::check_op(&mut heap, &mut model, &op) {
+            panic!("{:?}: {}", op, reason);
+        }
+        retired.extend(leaving);
+        assert!(heap.is_heap(), "heap property violated after {:?}", op);
+    }
+});
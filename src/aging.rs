@@ -0,0 +1,95 @@
+use crate::priority::Priority;
+
+/// A base priority and the tick it was queued at, the key an Aging queue
+/// ranks by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aged {
+    pub priority: i64,
+    pub pushed_at: u64,
+}
+
+/// Aging policy: waiting raises an entry's effective priority by `rate` per
+/// tick, so low-priority entries cannot starve forever. Plug it into any
+/// queue that takes a Priority, e.g. `KeyedQueue<Aged, V, Aging>` or
+/// `Heap<Aged, Aging>`.
+///
+/// Every entry ages at the same rate, so the order between two entries never
+/// changes while they wait: effective priority at tick `now` is
+/// `priority + rate * (now - pushed_at)`, which ranks entries exactly like
+/// the fixed key `priority - rate * pushed_at`. Entries are compared by that
+/// key, so aging costs nothing per tick and nothing is ever re-sifted.
+/// With a rate of 0 this is plain max-priority order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aging {
+    rate: i64,
+}
+
+impl Aging {
+    /// Creates a policy under which entries gain `rate` priority per tick.
+    ///
+    /// # Edge cases
+    /// * Negative rates are treated as 0, since they would make waiting
+    ///   entries sink instead of rise
+    pub fn new(rate: i64) -> Self {
+        Self { rate: rate.max(0) }
+    }
+
+    /// Returns the entry's effective priority as of tick `now`.
+    pub fn effective(&self, aged: &Aged, now: u64) -> i128 {
+        self.fixed_key(aged) + self.rate as i128 * now as i128
+    }
+
+    fn fixed_key(&self, aged: &Aged) -> i128 {
+        aged.priority as i128 - self.rate as i128 * aged.pushed_at as i128
+    }
+}
+
+impl Priority<Aged> for Aging {
+    fn higher_than(&self, a: &Aged, b: &Aged) -> bool {
+        self.fixed_key(a) > self.fixed_key(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyed::KeyedQueue;
+
+    fn aged(priority: i64, pushed_at: u64) -> Aged {
+        Aged {
+            priority,
+            pushed_at,
+        }
+    }
+
+    #[test]
+    fn test_old_low_priority_entry_overtakes() {
+        let aging = Aging::new(2);
+        let mut queue = KeyedQueue::with_priority(aging);
+        queue.push(aged(1, 0), "old batch job");
+        queue.push(aged(10, 5), "new urgent job");
+        queue.push(aged(10, 5), "newer urgent job");
+
+        // At tick 5 the batch job has aged to 1 + 2 * 5 = 11
+        assert_eq!(
+            queue.peek().map(|(key, _)| aging.effective(key, 5)),
+            Some(11)
+        );
+        assert_eq!(queue.pop(), Some((aged(1, 0), "old batch job")));
+        assert_eq!(queue.pop(), Some((aged(10, 5), "new urgent job")));
+        assert_eq!(queue.pop(), Some((aged(10, 5), "newer urgent job")));
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn test_zero_rate_is_plain_priority() {
+        let mut queue = KeyedQueue::with_priority(Aging::new(0));
+        queue.push(aged(3, 0), 'a');
+        queue.push(aged(7, 100), 'b');
+        queue.push(aged(3, 200), 'c');
+        let order: Vec<char> = std::iter::from_fn(|| queue.pop().map(|(_, c)| c)).collect();
+        assert_eq!(order, vec!['b', 'a', 'c']);
+        assert!(queue.is_empty());
+        assert_eq!(Aging::new(-5), Aging::new(0));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::handles::Handle;
+use crate::heap::{Heap, HeapError};
+use crate::priority::MinOrder;
+
+/// Branching factor of the vertex queues; 4 keeps the frequent key changes
+/// shallow and takes the unrolled sift_down_fixed path.
+const D: u32 = 4;
+
+/// Vertices waiting in a queue, nearest first, as (distance, vertex). Each
+/// queued vertex keeps its handle so a shorter distance can be applied with
+/// increase_key, which under MinOrder moves it towards the root.
+struct VertexQueue {
+    heap: Heap<(u64, usize), MinOrder>,
+    handles: Vec<Option<Handle>>,
+}
+
+impl VertexQueue {
+    fn new(vertices: usize) -> Result<Self, HeapError> {
+        Ok(Self {
+            heap: Heap::from_vec(D, Vec::new(), MinOrder)?,
+            handles: vec![None; vertices],
+        })
+    }
+
+    /// Queues `vertex` at `distance`, or moves it up if it is already queued
+    /// further away. Returns true if the vertex's entry changed.
+    fn offer(&mut self, vertex: usize, distance: u64) -> Result<bool, HeapError> {
+        match self.handles[vertex] {
+            Some(handle) => {
+                if self.heap.get(handle)?.0 <= distance {
+                    return Ok(false);
+                }
+                self.heap.increase_key(handle, (distance, vertex))?;
+            }
+            None => self.handles[vertex] = Some(self.heap.insert((distance, vertex))?),
+        }
+        Ok(true)
+    }
+
+    /// Removes the nearest vertex, forgetting its handle.
+    fn pop(&mut self) -> Option<(u64, usize)> {
+        let (distance, vertex) = self.heap.extract_max().ok()?;
+        self.handles[vertex] = None;
+        Some((distance, vertex))
+    }
+}
+
+/// Checks that every edge of the adjacency list leads to a vertex of it.
+///
+/// # Edge cases
+/// * Returns IndexOutOfRange error with the first bad target otherwise
+fn check_edges(graph: &[Vec<(usize, u64)>]) -> Result<(), HeapError> {
+    let size = graph.len();
+    match graph.iter().flatten().find(|&&(to, _)| to >= size) {
+        Some(&(idx, _)) => Err(HeapError::IndexOutOfRange { idx, size }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the shortest distance from `source` to every vertex, with None
+/// for vertices it cannot reach. `graph[u]` lists `(v, weight)` for every
+/// edge from u to v. Runs in O((V + E) log V) on the crate's Heap, lowering
+/// a queued vertex's distance by handle instead of queueing it again.
+///
+/// # Edge cases
+/// * Returns IndexOutOfRange error if source or an edge's target is not a
+///   vertex of the graph
+/// * Paths longer than u64::MAX saturate at u64::MAX
+pub fn dijkstra(graph: &[Vec<(usize, u64)>], source: usize) -> Result<Vec<Option<u64>>, HeapError> {
+    if source >= graph.len() {
+        return Err(HeapError::IndexOutOfRange {
+            idx: source,
+            size: graph.len(),
+        });
+    }
+    check_edges(graph)?;
+
+    let mut distances = vec![None; graph.len()];
+    let mut queue = VertexQueue::new(graph.len())?;
+    queue.offer(source, 0)?;
+    while let Some((distance, vertex)) = queue.pop() {
+        distances[vertex] = Some(distance);
+        for &(to, weight) in &graph[vertex] {
+            if distances[to].is_none() {
+                queue.offer(to, distance.saturating_add(weight))?;
+            }
+        }
+    }
+    Ok(distances)
+}
+
+/// A minimum spanning tree, or forest if the graph is disconnected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanningTree {
+    /// The chosen edges as (from, to, weight), in the order Prim added them.
+    pub edges: Vec<(usize, usize, u64)>,
+    /// Sum of the chosen edges' weights.
+    pub weight: u64,
+}
+
+/// Returns a minimum spanning tree of an undirected graph with Prim's
+/// algorithm, in O((V + E) log V) on the crate's Heap. `graph[u]` lists
+/// `(v, weight)` for the edges at u; an edge only listed at one end is
+/// treated as undirected all the same.
+///
+/// # Edge cases
+/// * Returns IndexOutOfRange error if an edge's target is not a vertex
+/// * A disconnected graph gets a spanning forest, grown from the lowest
+///   numbered vertex of each component
+/// * Self-loops are never chosen
+pub fn prim(graph: &[Vec<(usize, u64)>]) -> Result<SpanningTree, HeapError> {
+    check_edges(graph)?;
+
+    // Both directions of every edge, so one-sided listings still connect
+    let mut adjacent = vec![Vec::new(); graph.len()];
+    for (from, edges) in graph.iter().enumerate() {
+        for &(to, weight) in edges {
+            adjacent[from].push((to, weight));
+            adjacent[to].push((from, weight));
+        }
+    }
+
+    let mut tree = SpanningTree {
+        edges: Vec::new(),
+        weight: 0,
+    };
+    let mut in_tree = vec![false; graph.len()];
+    let mut parent = vec![0; graph.len()];
+    let mut queue = VertexQueue::new(graph.len())?;
+
+    for root in 0..graph.len() {
+        if in_tree[root] {
+            continue;
+        }
+        queue.offer(root, 0)?;
+        parent[root] = root;
+        while let Some((weight, vertex)) = queue.pop() {
+            in_tree[vertex] = true;
+            if parent[vertex] != vertex {
+                tree.edges.push((parent[vertex], vertex, weight));
+                tree.weight = tree.weight.saturating_add(weight);
+            }
+            for &(to, weight) in &adjacent[vertex] {
+                if !in_tree[to] && queue.offer(to, weight)? {
+                    parent[to] = vertex;
+                }
+            }
+        }
+    }
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    /// A random directed graph with up to `max_edges` edges.
+    fn random_graph(rng: &mut Rng, vertices: usize, max_edges: u64) -> Vec<Vec<(usize, u64)>> {
+        let mut graph = vec![Vec::new(); vertices];
+        for _ in 0..rng.below(max_edges + 1) {
+            let from = rng.below(vertices as u64) as usize;
+            let to = rng.below(vertices as u64) as usize;
+            graph[from].push((to, rng.below(20)));
+        }
+        graph
+    }
+
+    fn bellman_ford(graph: &[Vec<(usize, u64)>], source: usize) -> Vec<Option<u64>> {
+        let mut distances = vec![None; graph.len()];
+        distances[source] = Some(0);
+        for _ in 0..graph.len() {
+            for (from, edges) in graph.iter().enumerate() {
+                let Some(base) = distances[from] else {
+                    continue;
+                };
+                for &(to, weight) in edges {
+                    let through = base + weight;
+                    if distances[to].is_none_or(|d| through < d) {
+                        distances[to] = Some(through);
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    fn find(parents: &[usize], mut v: usize) -> usize {
+        while parents[v] != v {
+            v = parents[v];
+        }
+        v
+    }
+
+    /// Components left after joining the edges, or None if they form a cycle.
+    fn components_if_acyclic(vertices: usize, edges: &[(usize, usize, u64)]) -> Option<usize> {
+        let mut parents: Vec<usize> = (0..vertices).collect();
+        for &(a, b, _) in edges {
+            let (ra, rb) = (find(&parents, a), find(&parents, b));
+            if ra == rb {
+                return None;
+            }
+            parents[ra] = rb;
+        }
+        Some(vertices - edges.len())
+    }
+
+    /// The lightest forest with as few components as the graph allows, by
+    /// trying every subset of edges.
+    fn brute_force_mst(graph: &[Vec<(usize, u64)>]) -> u64 {
+        let edges: Vec<(usize, usize, u64)> = graph
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |&(to, w)| (from, to, w)))
+            .collect();
+        let mut best: Option<(usize, u64)> = None;
+        for mask in 0u32..(1 << edges.len()) {
+            let chosen: Vec<(usize, usize, u64)> = (0..edges.len())
+                .filter(|&i| mask & (1 << i) != 0)
+                .map(|i| edges[i])
+                .collect();
+            if let Some(components) = components_if_acyclic(graph.len(), &chosen) {
+                let weight = chosen.iter().map(|&(_, _, w)| w).sum();
+                if best.is_none_or(|b| (components, weight) < b) {
+                    best = Some((components, weight));
+                }
+            }
+        }
+        best.map_or(0, |(_, weight)| weight)
+    }
+
+    #[test]
+    fn test_dijkstra_small_graph() {
+        let graph = vec![
+            vec![(1, 7), (2, 9), (5, 14)],
+            vec![(0, 7), (2, 10), (3, 15)],
+            vec![(0, 9), (1, 10), (3, 11), (5, 2)],
+            vec![(1, 15), (2, 11), (4, 6)],
+            vec![(3, 6), (5, 9)],
+            vec![(0, 14), (2, 2), (4, 9)],
+            vec![],
+        ];
+        assert_eq!(
+            dijkstra(&graph, 0),
+            Ok(vec![
+                Some(0),
+                Some(7),
+                Some(9),
+                Some(20),
+                Some(20),
+                Some(11),
+                None
+            ])
+        );
+        assert_eq!(
+            dijkstra(&graph, 7),
+            Err(HeapError::IndexOutOfRange { idx: 7, size: 7 })
+        );
+        assert_eq!(
+            dijkstra(&[vec![(3, 1)]], 0),
+            Err(HeapError::IndexOutOfRange { idx: 3, size: 1 })
+        );
+    }
+
+    #[test]
+    fn test_dijkstra_matches_bellman_ford() {
+        let mut rng = Rng::new(5);
+        for _ in 0..200 {
+            let vertices = 1 + rng.below(12) as usize;
+            let graph = random_graph(&mut rng, vertices, 40);
+            let source = rng.below(vertices as u64) as usize;
+            assert_eq!(dijkstra(&graph, source), Ok(bellman_ford(&graph, source)));
+        }
+    }
+
+    #[test]
+    fn test_prim_small_graph() {
+        // A square with one diagonal, and a separate edge 4-5
+        let graph = vec![
+            vec![(1, 1), (3, 4), (2, 3)],
+            vec![(2, 2)],
+            vec![(3, 5)],
+            vec![],
+            vec![(5, 7), (4, 1)],
+            vec![],
+        ];
+        let tree = prim(&graph).unwrap();
+        assert_eq!(tree.edges, vec![(0, 1, 1), (1, 2, 2), (0, 3, 4), (4, 5, 7)]);
+        assert_eq!(tree.weight, 14);
+        assert_eq!(prim(&[]).unwrap().edges, vec![]);
+    }
+
+    #[test]
+    fn test_prim_matches_brute_force() {
+        let mut rng = Rng::new(11);
+        for _ in 0..150 {
+            let vertices = 1 + rng.below(6) as usize;
+            let graph = random_graph(&mut rng, vertices, 10);
+            let tree = prim(&graph).unwrap();
+            assert_eq!(tree.weight, brute_force_mst(&graph));
+            assert_eq!(
+                tree.weight,
+                tree.edges.iter().map(|&(_, _, w)| w).sum::<u64>()
+            );
+            let components = components_if_acyclic(vertices, &tree.edges);
+            assert!(components.is_some());
+            for &(a, b, w) in &tree.edges {
+                let listed = graph[a].contains(&(b, w)) || graph[b].contains(&(a, w));
+                assert!(listed);
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::heap::Heap;
+use std::fs;
+use std::io::{self, Read, Write};
+
+/// One line of a batch script.
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Build(u32, Vec<i32>),
+    Insert(i32),
+    ExtractMax,
+    ChangeD(u32),
+    Print,
+    Sort,
+}
+
+/// Parses a command line such as `build d=3 5 2 9 1`, `insert 42`,
+/// `extract-max`, `change-d 4`, `print` or `sort`.
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let args: Vec<&str> = words.collect();
+    let number = |text: &str| {
+        text.parse::<i32>()
+            .map_err(|_| format!("{}: invalid number {}", name, text))
+    };
+    let d_value = |text: &str| {
+        text.parse::<u32>()
+            .map_err(|_| format!("{}: invalid d {}", name, text))
+    };
+
+    match (name, args.as_slice()) {
+        ("build", [d, items @ ..]) => {
+            let d = d
+                .strip_prefix("d=")
+                .ok_or_else(|| "build: expected d=<n> first".to_string())?;
+            let items = items.iter().map(|x| number(x)).collect::<Result<_, _>>()?;
+            Ok(Command::Build(d_value(d)?, items))
+        }
+        ("insert", [value]) => Ok(Command::Insert(number(value)?)),
+        ("extract-max", []) => Ok(Command::ExtractMax),
+        ("change-d", [d]) => Ok(Command::ChangeD(d_value(d)?)),
+        ("print", []) => Ok(Command::Print),
+        ("sort", []) => Ok(Command::Sort),
+        ("build" | "insert" | "extract-max" | "change-d" | "print" | "sort", _) => {
+            Err(format!("{}: wrong number of arguments", name))
+        }
+        _ => Err(format!("unknown command {}", name)),
+    }
+}
+
+fn join(values: impl Iterator<Item = i32>) -> String {
+    values.map(|x| x.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Prints the fields every heap-changing command ends with.
+fn state(heap: &Heap) -> String {
+    format!(
+        "size={} array={}",
+        heap.len(),
+        join(heap.as_slice().iter().copied())
+    )
+}
+
+fn built(heap: &mut Option<Heap>) -> Result<&mut Heap, String> {
+    heap.as_mut()
+        .ok_or_else(|| "no heap yet, build one first".to_string())
+}
+
+/// Runs the command and returns its output line.
+fn execute(command: Command, current: &mut Option<Heap>) -> Result<String, String> {
+    match command {
+        Command::Build(d, items) => {
+            let heap = Heap::new(d, &items).map_err(|e| format!("build: {}", e))?;
+            let line = format!("build d={} {}", d, state(&heap));
+            *current = Some(heap);
+            Ok(line)
+        }
+        Command::Insert(value) => {
+            let heap = built(current)?;
+            heap.insert(value).map_err(|e| format!("insert: {}", e))?;
+            Ok(format!("insert value={} {}", value, state(heap)))
+        }
+        Command::ExtractMax => {
+            let heap = built(current)?;
+            let max = heap
+                .extract_max()
+                .map_err(|e| format!("extract-max: {}", e))?;
+            Ok(format!("extract-max value={} {}", max, state(heap)))
+        }
+        Command::ChangeD(d) => {
+            let heap = built(current)?;
+            heap.change_d(d).map_err(|e| format!("change-d: {}", e))?;
+            Ok(format!("change-d d={} {}", d, state(heap)))
+        }
+        Command::Print => {
+            let heap = built(current)?;
+            Ok(format!("print d={} {}", heap.d(), state(heap)))
+        }
+        Command::Sort => {
+            let heap = built(current)?;
+            Ok(format!("sort values={}", join(heap.sorted_iter().copied())))
+        }
+    }
+}
+
+/// Runs a whole script, writing one line per command to `out`. Blank lines
+/// and lines starting with `#` are skipped.
+///
+/// # Edge cases
+/// * Stops at the first command that doesn't parse or fails on the heap,
+///   returning the error prefixed with its line number
+pub fn run_script(script: &str, out: &mut impl Write) -> Result<(), String> {
+    let mut heap = None;
+    for (n, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let output = parse_command(line)
+            .and_then(|command| execute(command, &mut heap))
+            .map_err(|e| format!("line {}: {}", n + 1, e))?;
+        writeln!(out, "{}", output).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Runs the script in the file named by the only argument, or the one
+/// piped to stdin when there is none.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let script = match args {
+        [] => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            text
+        }
+        [path] => {
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+        }
+        _ => return Err("Usage: batch [script]".to_string()),
+    };
+
+    run_script(&script, &mut io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("build d=3 5 2 -9"),
+            Ok(Command::Build(3, vec![5, 2, -9]))
+        );
+        assert_eq!(parse_command("build d=2"), Ok(Command::Build(2, vec![])));
+        assert_eq!(parse_command("  insert   42 "), Ok(Command::Insert(42)));
+        assert_eq!(parse_command("change-d 4"), Ok(Command::ChangeD(4)));
+        assert_eq!(parse_command("sort"), Ok(Command::Sort));
+
+        assert!(parse_command("build 3 5").is_err());
+        assert!(parse_command("insert").is_err());
+        assert!(parse_command("insert x").is_err());
+        assert!(parse_command("print now").is_err());
+        assert_eq!(parse_command("pop"), Err("unknown command pop".to_string()));
+    }
+
+    #[test]
+    fn test_run_script_stops_at_first_error() {
+        let mut out = Vec::new();
+        let result = run_script("build d=2 1\nextract-max\nextract-max\nprint\n", &mut out);
+        assert_eq!(
+            result,
+            Err("line 3: extract-max: heap is empty".to_string())
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "build d=2 size=1 array=1\nextract-max value=1 size=0 array=\n"
+        );
+
+        let mut out = Vec::new();
+        assert_eq!(
+            run_script("# nothing built\nprint\n", &mut out),
+            Err("line 2: no heap yet, build one first".to_string())
+        );
+    }
+}
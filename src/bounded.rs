@@ -0,0 +1,375 @@
+use crate::handles::Handle;
+use crate::heap::{Heap, HeapError};
+use crate::priority::Priority;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+/// What a BoundedHeap does with an insert once it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Refuse the new item with a HeapFull error.
+    Reject,
+    /// Drop whichever of the new item and the current lowest-priority
+    /// element ranks lower.
+    EvictMin,
+    /// Drop the element that has been in the heap the longest.
+    EvictOldest,
+}
+
+impl OverflowPolicy {
+    /// Parses `reject`, `evict-min` or `evict-oldest`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "reject" => Ok(OverflowPolicy::Reject),
+            "evict-min" => Ok(OverflowPolicy::EvictMin),
+            "evict-oldest" => Ok(OverflowPolicy::EvictOldest),
+            other => Err(format!("Unknown overflow policy: {}", other)),
+        }
+    }
+}
+
+/// What BoundedHeap::insert did with the new item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inserted {
+    /// The new item's handle, or None if EvictMin dropped it straight away.
+    pub handle: Option<Handle>,
+    /// The element dropped to make room, if any; under EvictMin this may be
+    /// the new item itself.
+    pub evicted: Option<i32>,
+}
+
+/// A heap that applies an OverflowPolicy when full, keeping the arrival order
+/// of its elements so the oldest one can be found.
+///
+/// Reads go to the inner heap through `Deref`; every mutation goes through
+/// this wrapper so the arrival order stays in step with the heap. Arrivals
+/// are tracked by handle, so a key change keeps an element's place in the
+/// order and equal values are never mistaken for each other. Handles of
+/// elements that left are dropped lazily: they stay queued, stale, until
+/// they reach the front or the queue grows to twice the heap's length.
+pub struct BoundedHeap<P: Priority> {
+    heap: Heap<i32, P>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    arrivals: VecDeque<Handle>,
+}
+
+impl<P: Priority> BoundedHeap<P> {
+    /// Builds a heap of at most `capacity` elements from the items in arrival
+    /// order. Items beyond capacity go through `policy` one by one, and
+    /// whatever it dropped is returned.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    pub fn build(
+        d: u32,
+        capacity: usize,
+        items: &[i32],
+        priority: P,
+        policy: OverflowPolicy,
+    ) -> Result<(Self, Vec<i32>), HeapError> {
+        let heap = Heap::bounded(d, capacity, items, priority)?;
+        let mut arrivals: Vec<Handle> = heap.handles().map(|(handle, _)| handle).collect();
+        arrivals.sort();
+        let kept = heap.len();
+        let mut bounded = Self {
+            heap,
+            capacity,
+            policy,
+            arrivals: arrivals.into(),
+        };
+
+        let mut dropped = Vec::new();
+        for &item in &items[kept..] {
+            match bounded.insert(item) {
+                Ok(Inserted {
+                    evicted: Some(evicted),
+                    ..
+                }) => dropped.push(evicted),
+                Ok(_) => {}
+                Err(HeapError::HeapFull { .. }) => dropped.push(item),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((bounded, dropped))
+    }
+
+    /// Returns the most elements the heap holds before the policy applies.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the bytes allocated outside the struct: the heap's, see
+    /// Heap::heap_bytes, plus the arrival order.
+    pub fn heap_bytes(&self) -> usize {
+        self.heap.heap_bytes() + self.arrivals.capacity() * std::mem::size_of::<Handle>()
+    }
+
+    /// Returns the policy applied when the heap is full.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Inserts the item, applying the overflow policy if the heap is full.
+    ///
+    /// # Edge cases
+    /// * Returns HeapFull error under Reject, leaving the heap unchanged
+    /// * Returns Inconsistent error if the oldest arrival is missing from
+    ///   the heap, which would be a bug in this wrapper
+    pub fn insert(&mut self, item: i32) -> Result<Inserted, HeapError> {
+        let mut evicted = None;
+        if self.heap.len() >= self.capacity {
+            let victim = match self.policy {
+                OverflowPolicy::Reject => {
+                    return Err(HeapError::HeapFull {
+                        capacity: self.capacity,
+                    })
+                }
+                OverflowPolicy::EvictMin => match self.heap.lowest() {
+                    Some((idx, lowest)) if self.heap.outranks(&item, lowest) => {
+                        self.heap.handle_at(idx)?
+                    }
+                    _ => {
+                        return Ok(Inserted {
+                            handle: None,
+                            evicted: Some(item),
+                        })
+                    }
+                },
+                OverflowPolicy::EvictOldest => match self.oldest() {
+                    Some(&oldest) => oldest,
+                    None => {
+                        return Ok(Inserted {
+                            handle: None,
+                            evicted: Some(item),
+                        })
+                    }
+                },
+            };
+            self.position_of(victim)?;
+            evicted = Some(self.delete(victim)?);
+        }
+
+        let handle = self.heap.insert(item)?;
+        self.arrivals.push_back(handle);
+        Ok(Inserted {
+            handle: Some(handle),
+            evicted,
+        })
+    }
+
+    /// Removes and returns the root.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty
+    pub fn extract_max(&mut self) -> Result<i32, HeapError> {
+        let max = self.heap.extract_max()?;
+        self.forget();
+        Ok(max)
+    }
+
+    /// Changes the branching factor; arrival order is unaffected.
+    pub fn change_d(&mut self, d: u32) -> Result<(), HeapError> {
+        self.heap.change_d(d)
+    }
+
+    /// Raises the priority of the handle's element; see Heap::increase_key.
+    pub fn increase_key(&mut self, handle: Handle, value: i32) -> Result<i32, HeapError> {
+        self.heap.increase_key(handle, value)
+    }
+
+    /// Lowers the priority of the handle's element; see Heap::decrease_key.
+    pub fn decrease_key(&mut self, handle: Handle, value: i32) -> Result<i32, HeapError> {
+        self.heap.decrease_key(handle, value)
+    }
+
+    /// Removes and returns the handle's element.
+    ///
+    /// # Edge cases
+    /// * Returns StaleHandle error if the element has already left the heap,
+    ///   and UnknownHandle error if the handle was never issued by it
+    pub fn delete(&mut self, handle: Handle) -> Result<i32, HeapError> {
+        let removed = self.heap.delete(handle)?;
+        self.forget();
+        Ok(removed)
+    }
+
+    /// Restores the heap property, returning how many violations were found.
+    pub fn repair(&mut self) -> Result<usize, HeapError> {
+        self.heap.repair()
+    }
+
+    /// Returns the slot of an element this wrapper is tracking.
+    ///
+    /// # Edge cases
+    /// * Returns Inconsistent error if the handle is no longer in the heap
+    fn position_of(&self, handle: Handle) -> Result<usize, HeapError> {
+        self.heap
+            .position(handle)
+            .map_err(|_| HeapError::Inconsistent {
+                reason: "a tracked arrival is missing from the heap",
+            })
+    }
+
+    /// Returns the oldest arrival still in the heap, dropping the stale
+    /// handles queued ahead of it.
+    fn oldest(&mut self) -> Option<&Handle> {
+        while let Some(&front) = self.arrivals.front() {
+            if self.heap.position(front).is_ok() {
+                break;
+            }
+            self.arrivals.pop_front();
+        }
+        self.arrivals.front()
+    }
+
+    /// Records that an element left the heap. Its handle is now stale, so it
+    /// stays queued until oldest skips it; the queue is compacted once stale
+    /// handles outnumber live ones, keeping this amortised O(1).
+    fn forget(&mut self) {
+        if self.arrivals.len() > 2 * self.heap.len() {
+            let heap = &self.heap;
+            self.arrivals.retain(|&h| heap.position(h).is_ok());
+        }
+    }
+}
+
+impl<P: Priority> Deref for BoundedHeap<P> {
+    type Target = Heap<i32, P>;
+
+    fn deref(&self) -> &Heap<i32, P> {
+        &self.heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heap::HEAP_MAX_SIZE;
+    use crate::priority::MaxOrder;
+
+    fn full(policy: OverflowPolicy) -> BoundedHeap<MaxOrder> {
+        let items: Vec<i32> = (0..HEAP_MAX_SIZE as i32).map(|x| x + 100).collect();
+        let (heap, dropped) =
+            BoundedHeap::build(2, HEAP_MAX_SIZE, &items, MaxOrder, policy).unwrap();
+        assert!(dropped.is_empty());
+        heap
+    }
+
+    fn evicted(inserted: Result<Inserted, HeapError>) -> Result<Option<i32>, HeapError> {
+        inserted.map(|inserted| inserted.evicted)
+    }
+
+    #[test]
+    fn test_reject() {
+        let mut heap = full(OverflowPolicy::Reject);
+        assert_eq!(
+            heap.insert(5),
+            Err(HeapError::HeapFull {
+                capacity: HEAP_MAX_SIZE
+            })
+        );
+        assert_eq!(heap.len(), HEAP_MAX_SIZE);
+    }
+
+    #[test]
+    fn test_evict_min() {
+        let mut heap = full(OverflowPolicy::EvictMin);
+        assert_eq!(
+            heap.insert(5),
+            Ok(Inserted {
+                handle: None,
+                evicted: Some(5)
+            })
+        );
+        assert_eq!(evicted(heap.insert(5000)), Ok(Some(100)));
+        assert_eq!(heap.peek_max(), Ok(&5000));
+        assert_eq!(heap.lowest().map(|(_, &x)| x), Some(101));
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_evict_oldest() {
+        let mut heap = full(OverflowPolicy::EvictOldest);
+        assert_eq!(heap.extract_max(), Ok(1099));
+        assert_eq!(evicted(heap.insert(7)), Ok(None));
+        assert_eq!(evicted(heap.insert(8)), Ok(Some(100)));
+        assert_eq!(evicted(heap.insert(9)), Ok(Some(101)));
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_key_changes_keep_arrival_order() {
+        let mut heap = full(OverflowPolicy::EvictOldest);
+        let slot = heap.as_slice().iter().position(|&x| x == 100).unwrap();
+        let first = heap.handle_at(slot).unwrap();
+        assert_eq!(heap.increase_key(first, 5000), Ok(100));
+        let slot = heap.as_slice().iter().position(|&x| x == 101).unwrap();
+        assert_eq!(heap.delete(heap.handle_at(slot).unwrap()), Ok(101));
+        assert_eq!(evicted(heap.insert(1)), Ok(None));
+        // The changed element is still the oldest arrival
+        assert_eq!(evicted(heap.insert(2)), Ok(Some(5000)));
+        assert_eq!(heap.get(first).ok(), None);
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_duplicates_keep_their_own_arrival() {
+        let (mut heap, _) =
+            BoundedHeap::build(2, 3, &[7, 7, 3], MaxOrder, OverflowPolicy::EvictOldest).unwrap();
+        let mut handles: Vec<Handle> = heap.handles().map(|(h, _)| h).collect();
+        handles.sort();
+        // Raise the second 7; the first one is still the oldest arrival
+        assert_eq!(heap.increase_key(handles[1], 9), Ok(7));
+        assert_eq!(evicted(heap.insert(1)), Ok(Some(7)));
+        assert_eq!(heap.get(handles[1]), Ok(&9));
+        assert_eq!(heap.get(handles[0]).ok(), None);
+
+        // Lower the remaining 9 back to 7 and add another 7: eviction still
+        // follows arrival order, not the first matching value
+        assert_eq!(heap.decrease_key(handles[1], 7), Ok(9));
+        assert_eq!(evicted(heap.insert(7)), Ok(Some(7)));
+        assert_eq!(heap.get(handles[1]).ok(), None);
+        assert_eq!(evicted(heap.insert(0)), Ok(Some(3)));
+        assert_eq!(
+            heap.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![7, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_stale_arrivals_are_skipped_and_compacted() {
+        let (mut heap, _) =
+            BoundedHeap::build(3, 8, &[], MaxOrder, OverflowPolicy::EvictOldest).unwrap();
+        let mut expected: VecDeque<(Handle, i32)> = VecDeque::new();
+        let mut rng = crate::rng::Rng::new(9);
+        for step in 0..2000 {
+            let item = rng.range_i32(-100, 100);
+            if step % 3 == 0 && !heap.is_empty() {
+                // Remove from the middle, leaving a stale handle queued
+                let (handle, _) = expected.remove(expected.len() / 2).unwrap();
+                heap.delete(handle).unwrap();
+            } else {
+                let inserted = heap.insert(item).unwrap();
+                let oldest = (expected.len() == 8).then(|| expected.pop_front().unwrap().1);
+                assert_eq!(inserted.evicted, oldest);
+                expected.push_back((inserted.handle.unwrap(), item));
+            }
+            assert_eq!(heap.len(), expected.len());
+            assert!(heap.arrivals.len() <= 2 * heap.len() + 1);
+        }
+    }
+
+    #[test]
+    fn test_build_reports_dropped() {
+        let items: Vec<i32> = (0..HEAP_MAX_SIZE as i32 + 3).collect();
+        let (_, dropped) =
+            BoundedHeap::build(4, HEAP_MAX_SIZE, &items, MaxOrder, OverflowPolicy::Reject).unwrap();
+        assert_eq!(dropped, vec![1000, 1001, 1002]);
+        let (heap, dropped) =
+            BoundedHeap::build(4, HEAP_MAX_SIZE, &items, MaxOrder, OverflowPolicy::EvictMin)
+                .unwrap();
+        assert_eq!(dropped, vec![0, 1, 2]);
+        assert_eq!(heap.peek_max(), Ok(&1002));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::timestamp::Timestamp;
+
+/// Fewest buckets the calendar shrinks to.
+const MIN_BUCKETS: usize = 2;
+
+/// How many of the earliest events are sampled to pick a bucket width.
+const WIDTH_SAMPLE: usize = 25;
+
+/// A calendar queue (Brown, 1988) of events keyed by timestamp, earliest
+/// first, with FIFO order among equal timestamps.
+///
+/// Events are hashed into "days" of `width` time units, wrapping around a
+/// "year" of buckets. When timestamps are spread roughly evenly, push and
+/// pop touch one short bucket, which is O(1) on average instead of the
+/// O(log n) sifting a heap pays. The bucket count doubles or halves with the
+/// number of events, and every resize re-estimates the width from the
+/// spacing of the earliest events.
+pub struct CalendarQueue<T> {
+    /// Each bucket is sorted by descending time, so its earliest event is last.
+    buckets: Vec<Vec<(u64, T)>>,
+    width: u64,
+    len: usize,
+    /// Bucket the next pop starts scanning from, and the end of its current day.
+    current: usize,
+    bucket_top: u64,
+}
+
+impl<T> Default for CalendarQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> CalendarQueue<T> {
+    /// Creates an empty queue with unit-width days.
+    pub fn new() -> Self {
+        Self {
+            buckets: (0..MIN_BUCKETS).map(|_| Vec::new()).collect(),
+            width: 1,
+            len: 0,
+            current: 0,
+            bucket_top: 1,
+        }
+    }
+
+    fn bucket_of(&self, time: u64) -> usize {
+        ((time / self.width) % self.buckets.len() as u64) as usize
+    }
+
+    /// Makes the next pop start from the day containing `time`.
+    fn start_at(&mut self, time: u64) {
+        self.current = self.bucket_of(time);
+        self.bucket_top = (time / self.width)
+            .saturating_add(1)
+            .saturating_mul(self.width);
+    }
+
+    /// Schedules an item at `time`.
+    ///
+    /// # Edge cases
+    /// * A time earlier than the last popped one is allowed; scanning restarts
+    ///   from its day
+    pub fn push(&mut self, time: u64, item: T) {
+        if self.len == 0 || time < self.bucket_top.saturating_sub(self.width) {
+            self.start_at(time);
+        }
+        self.insert(time, item);
+        self.len += 1;
+        if self.len > 2 * self.buckets.len() {
+            self.resize(2 * self.buckets.len());
+        }
+    }
+
+    /// Schedules an item at a Timestamp, such as a `chrono::DateTime<Utc>`
+    /// with the chrono feature; see push.
+    pub fn push_at(&mut self, at: &impl Timestamp, item: T) {
+        self.push(at.to_ticks(), item);
+    }
+
+    fn insert(&mut self, time: u64, item: T) {
+        let bucket = self.bucket_of(time);
+        let bucket = &mut self.buckets[bucket];
+        // Ahead of equal times, so those already queued pop first
+        let pos = bucket.partition_point(|&(t, _)| t > time);
+        bucket.insert(pos, (time, item));
+    }
+
+    /// Removes and returns the earliest event, or None if the queue is empty.
+    pub fn pop(&mut self) -> Option<(u64, T)> {
+        self.seek_earliest()?;
+        self.take_current()
+    }
+
+    /// Removes and returns the earliest event if its time is at or before
+    /// `now`, for callers that poll a clock instead of jumping to each event.
+    pub fn pop_due(&mut self, now: u64) -> Option<(u64, T)> {
+        match self.seek_earliest()? {
+            time if time <= now => self.take_current(),
+            _ => None,
+        }
+    }
+
+    /// Removes and returns the earliest event if it is due at `now`, with its
+    /// time converted back; see pop_due.
+    pub fn pop_due_at<S: Timestamp>(&mut self, now: &S) -> Option<(S, T)> {
+        let (time, item) = self.pop_due(now.to_ticks())?;
+        Some((S::from_ticks(time), item))
+    }
+
+    /// Moves the scan position to the day holding the earliest event and
+    /// returns that event's time, or None if the queue is empty.
+    fn seek_earliest(&mut self) -> Option<u64> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut bucket = self.current;
+        let mut top = self.bucket_top;
+        for _ in 0..self.buckets.len() {
+            if let Some(&(t, _)) = self.buckets[bucket].last() {
+                if t < top {
+                    self.current = bucket;
+                    self.bucket_top = top;
+                    return Some(t);
+                }
+            }
+            bucket = (bucket + 1) % self.buckets.len();
+            top = top.saturating_add(self.width);
+        }
+
+        // A whole year had nothing due; jump straight to the earliest event
+        let earliest = self
+            .buckets
+            .iter()
+            .filter_map(|b| b.last().map(|&(t, _)| t))
+            .min()?;
+        self.start_at(earliest);
+        Some(earliest)
+    }
+
+    /// Pops the earliest event of the current bucket, shrinking if needed.
+    fn take_current(&mut self) -> Option<(u64, T)> {
+        let event = self.buckets[self.current].pop()?;
+        self.len -= 1;
+        if self.buckets.len() > MIN_BUCKETS && self.len < self.buckets.len() / 2 {
+            self.resize(self.buckets.len() / 2);
+        }
+        Some(event)
+    }
+
+    /// Returns the number of queued events.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no events are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Rebuilds the calendar with `buckets` buckets and a width of three
+    /// times the average gap between the earliest events.
+    fn resize(&mut self, buckets: usize) {
+        // Buckets are reversed back into queue order first, then sorted
+        // stably, so equal times keep their FIFO order
+        let mut events: Vec<(u64, T)> = self
+            .buckets
+            .drain(..)
+            .flat_map(|bucket| bucket.into_iter().rev())
+            .collect();
+        events.sort_by_key(|&(t, _)| t);
+
+        let sample = &events[..events.len().min(WIDTH_SAMPLE)];
+        self.width = match (sample.first(), sample.last()) {
+            (Some(&(first, _)), Some(&(last, _))) if sample.len() > 1 => {
+                let average_gap = (last - first) / (sample.len() as u64 - 1);
+                average_gap.saturating_mul(3).max(1)
+            }
+            _ => self.width,
+        };
+
+        self.buckets = (0..buckets).map(|_| Vec::new()).collect();
+        if let Some(&(earliest, _)) = events.first() {
+            self.start_at(earliest);
+        }
+        for (time, item) in events {
+            self.insert(time, item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_pops_in_time_order() {
+        let mut rng = Rng::new(11);
+        let mut queue = CalendarQueue::new();
+        let mut expected = Vec::new();
+        for i in 0..2000 {
+            let time = rng.below(100_000);
+            queue.push(time, i);
+            expected.push((time, i));
+        }
+        expected.sort_by_key(|&(t, _)| t);
+
+        let popped: Vec<(u64, i32)> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(popped, expected);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_simulation_pattern() {
+        // Hold model: each popped event schedules a later one
+        let mut rng = Rng::new(3);
+        let mut queue = CalendarQueue::new();
+        for i in 0..500 {
+            queue.push(rng.below(1000), i);
+        }
+        let mut now = 0;
+        for i in 0..5000 {
+            let (time, _) = queue.pop().unwrap();
+            assert!(time >= now);
+            now = time;
+            queue.push(now + 1 + rng.below(1000), i);
+        }
+        assert_eq!(queue.len(), 500);
+    }
+
+    #[test]
+    fn test_sparse_times_and_ties() {
+        let mut queue = CalendarQueue::new();
+        queue.push(1_000_000_000, 'c');
+        queue.push(5, 'a');
+        queue.push(5, 'b');
+        queue.push(u64::MAX, 'd');
+        assert_eq!(queue.pop(), Some((5, 'a')));
+        assert_eq!(queue.pop(), Some((5, 'b')));
+        queue.push(7, 'e');
+        assert_eq!(queue.pop(), Some((7, 'e')));
+        assert_eq!(queue.pop(), Some((1_000_000_000, 'c')));
+        assert_eq!(queue.pop(), Some((u64::MAX, 'd')));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_pop_due() {
+        let mut queue = CalendarQueue::new();
+        for (time, name) in [(30, "c"), (10, "a"), (20, "b")] {
+            queue.push(time, name);
+        }
+        assert_eq!(queue.pop_due(5), None);
+        assert_eq!(queue.pop_due(20), Some((10, "a")));
+        assert_eq!(queue.pop_due(20), Some((20, "b")));
+        assert_eq!(queue.pop_due(29), None);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop_due(u64::MAX), Some((30, "c")));
+        assert_eq!(queue.pop_due(u64::MAX), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_events() {
+        use chrono::{DateTime, Utc};
+        let at = |text: &str| text.parse::<DateTime<Utc>>().unwrap();
+        let mut queue = CalendarQueue::new();
+        queue.push_at(&at("2026-10-14T09:00:00+02:00"), "standup");
+        queue.push_at(&at("2026-10-14T06:30:00Z"), "backup");
+        queue.push_at(&at("1999-12-31T23:59:59Z"), "overdue");
+
+        let now = at("2026-10-14T06:45:00Z");
+        assert_eq!(
+            queue.pop_due_at(&now),
+            Some((at("1999-12-31T23:59:59Z"), "overdue"))
+        );
+        assert_eq!(
+            queue.pop_due_at(&now),
+            Some((at("2026-10-14T06:30:00Z"), "backup"))
+        );
+        assert_eq!(queue.pop_due_at(&now), None);
+        assert_eq!(
+            queue
+                .pop_due_at(&at("2026-10-14T07:00:00Z"))
+                .map(|(_, e)| e),
+            Some("standup")
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_events() {
+        use time::format_description::well_known::Rfc3339;
+        use time::OffsetDateTime;
+        let at = |text: &str| OffsetDateTime::parse(text, &Rfc3339).unwrap();
+        let mut queue = CalendarQueue::new();
+        queue.push_at(&at("2026-10-14T12:00:00-05:00"), "late");
+        queue.push_at(&at("2026-10-14T12:00:00+05:00"), "early");
+        let end = at("2030-01-01T00:00:00Z");
+        assert_eq!(
+            queue.pop_due_at(&end),
+            Some((at("2026-10-14T07:00:00Z"), "early"))
+        );
+        assert_eq!(queue.pop_due_at(&end).map(|(_, e)| e), Some("late"));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::heap::HeapError;
+use crate::priority::{MaxOrder, Priority};
+
+/// A d-ary heap of `T` ordered by `P` whose branching factor D is fixed at
+/// compile time, so the son loop in sift_down has a constant trip count the
+/// compiler can fully unroll. Like Heap it is a Vec that grows as needed,
+/// and plain `ConstHeap<D>` is the i32 max-heap.
+///
+/// Use `Heap` instead when d is only known at runtime or needs to change.
+pub struct ConstHeap<const D: usize, T = i32, P: Priority<T> = MaxOrder> {
+    items: Vec<T>,
+    priority: P,
+}
+
+impl<const D: usize> ConstHeap<D> {
+    /// Creates a new D-ary max-heap from the given slice.
+    ///
+    /// # Edge cases
+    /// * Automatically builds a valid max-heap from the provided elements
+    pub fn new(slice: &[i32]) -> Self {
+        Self::with_priority(slice, MaxOrder)
+    }
+}
+
+impl<const D: usize, T: Clone, P: Priority<T>> ConstHeap<D, T, P> {
+    /// Creates a new D-ary heap from the given slice, ordered by `priority`.
+    pub fn with_priority(slice: &[T], priority: P) -> Self {
+        Self::from_vec(slice.to_vec(), priority)
+    }
+}
+
+impl<const D: usize, T, P: Priority<T>> ConstHeap<D, T, P> {
+    /// Rejects D = 0 at compile time, since such a heap has no sons to sift into.
+    const VALID_D: () = assert!(D >= 1, "D must be at least 1");
+
+    /// Creates a new D-ary heap from the items, ordered by `priority`. The
+    /// Vec's allocation becomes the heap's and is arranged in place.
+    pub fn from_vec(items: Vec<T>, priority: P) -> Self {
+        let () = Self::VALID_D;
+
+        let mut heap = Self { items, priority };
+        if heap.items.len() > 1 {
+            for i in (0..=(heap.items.len() - 2) / D).rev() {
+                heap.sift_down(i);
+            }
+        }
+        heap
+    }
+
+    /// Inserts a new item into the heap and maintains the heap property.
+    pub fn insert(&mut self, item: T) {
+        self.items.push(item);
+        self.sift_up(self.items.len() - 1);
+    }
+
+    /// Returns the highest-priority element without removing it.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty
+    pub fn peek_max(&self) -> Result<&T, HeapError> {
+        self.items.first().ok_or(HeapError::EmptyHeap)
+    }
+
+    /// Removes and returns the highest-priority element from the heap.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty
+    pub fn extract_max(&mut self) -> Result<T, HeapError> {
+        if self.items.is_empty() {
+            return Err(HeapError::EmptyHeap);
+        }
+
+        let max = self.items.swap_remove(0);
+        self.sift_down(0);
+        Ok(max)
+    }
+
+    /// Returns the number of elements currently in the heap.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true if the heap holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Checks that no son outranks its parent.
+    pub fn is_heap(&self) -> bool {
+        (1..self.items.len()).all(|i| {
+            !self
+                .priority
+                .higher_than(&self.items[i], &self.items[(i - 1) / D])
+        })
+    }
+
+    /// Moves the element at the given index down until no son outranks it.
+    fn sift_down(&mut self, mut idx: usize) {
+        let size = self.items.len();
+        loop {
+            let first_son = idx * D + 1;
+            if first_son >= size {
+                return;
+            }
+
+            let mut best_idx = idx;
+            let last_son = std::cmp::min(first_son + D, size);
+            for son_idx in first_son..last_son {
+                if self
+                    .priority
+                    .higher_than(&self.items[son_idx], &self.items[best_idx])
+                {
+                    best_idx = son_idx;
+                }
+            }
+
+            if best_idx == idx {
+                return;
+            }
+            self.items.swap(best_idx, idx);
+            idx = best_idx;
+        }
+    }
+
+    /// Moves the element at the given index up until its parent is not
+    /// outranked by it.
+    fn sift_up(&mut self, mut idx: usize) {
+        while idx > 0 {
+            let parent_idx = (idx - 1) / D;
+            if !self
+                .priority
+                .higher_than(&self.items[idx], &self.items[parent_idx])
+            {
+                return;
+            }
+            self.items.swap(parent_idx, idx);
+            idx = parent_idx;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heap::{Heap, HEAP_MAX_SIZE};
+    use crate::priority::MinOrder;
+
+    #[test]
+    fn test_const_heap_creation() {
+        let heap = ConstHeap::<3>::new(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(heap.len(), 8);
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_const_heap_insert_extract() {
+        let mut heap = ConstHeap::<4>::new(&[]);
+        assert!(heap.is_empty());
+        for x in [5, 20, 3, 8, 20, -1] {
+            heap.insert(x);
+        }
+        assert!(heap.is_heap());
+
+        let mut out = Vec::new();
+        while let Ok(max) = heap.extract_max() {
+            out.push(max);
+        }
+        assert_eq!(out, vec![20, 20, 8, 5, 3, -1]);
+        assert_eq!(heap.extract_max(), Err(HeapError::EmptyHeap));
+    }
+
+    #[test]
+    fn test_const_heap_matches_runtime_heap() {
+        let values = [7, -2, 9, 9, 0, 4, 13, 1, 8, 8, 2];
+        let mut fixed = ConstHeap::<3>::new(&values);
+        let mut runtime = Heap::new(3, &values).unwrap();
+        for _ in 0..values.len() {
+            assert_eq!(fixed.extract_max().ok(), runtime.extract_max().ok());
+        }
+    }
+
+    #[test]
+    fn test_grows_past_the_old_capacity_and_takes_any_order() {
+        let mut heap = ConstHeap::<2>::new(&[]);
+        for x in 0..(HEAP_MAX_SIZE as i32 * 3) {
+            heap.insert(x);
+        }
+        assert!(heap.is_heap());
+        assert_eq!(heap.peek_max(), Ok(&(HEAP_MAX_SIZE as i32 * 3 - 1)));
+
+        let words = ["pear", "fig", "apple", "kiwi"].map(String::from);
+        let mut by_min = ConstHeap::<3, String, MinOrder>::with_priority(&words, MinOrder);
+        let out: Vec<String> = std::iter::from_fn(|| by_min.extract_max().ok()).collect();
+        assert_eq!(out, ["apple", "fig", "kiwi", "pear"]);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::heap::{Heap, HeapError};
+use crate::priority::Priority;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A heap whose clones share storage until one of them is modified.
+///
+/// Cloning is O(1). The first mutation through a clone that still shares
+/// storage copies the heap, so branching queue state in a simulation only
+/// pays for the branches that actually diverge.
+pub struct CowHeap<P: Priority + Clone> {
+    heap: Arc<Heap<i32, P>>,
+}
+
+impl<P: Priority + Clone> CowHeap<P> {
+    /// Wraps the heap in storage no other clone shares yet.
+    pub fn new(heap: Heap<i32, P>) -> Self {
+        Self {
+            heap: Arc::new(heap),
+        }
+    }
+
+    /// Returns the heap for modification, copying it first if any other
+    /// clone still shares it.
+    pub fn make_mut(&mut self) -> &mut Heap<i32, P> {
+        Arc::make_mut(&mut self.heap)
+    }
+
+    /// Returns true if another clone currently shares this storage.
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.heap) > 1
+    }
+
+    /// Inserts the item, copying the storage first if it is shared.
+    ///
+    /// # Edge cases
+    /// * Returns HeapFull error if the heap is already full
+    pub fn insert(&mut self, item: i32) -> Result<(), HeapError> {
+        self.make_mut().insert(item).map(|_| ())
+    }
+
+    /// Removes and returns the root, copying the storage first if it is shared.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error without copying if the heap is empty
+    pub fn extract_max(&mut self) -> Result<i32, HeapError> {
+        if self.heap.is_empty() {
+            return Err(HeapError::EmptyHeap);
+        }
+        self.make_mut().extract_max()
+    }
+
+    /// Unwraps the heap, copying it only if another clone still shares it.
+    pub fn into_heap(self) -> Heap<i32, P> {
+        Arc::try_unwrap(self.heap).unwrap_or_else(|shared| Heap::clone(&shared))
+    }
+}
+
+impl<P: Priority + Clone> Clone for CowHeap<P> {
+    fn clone(&self) -> Self {
+        Self {
+            heap: Arc::clone(&self.heap),
+        }
+    }
+}
+
+impl<P: Priority + Clone> Deref for CowHeap<P> {
+    type Target = Heap<i32, P>;
+
+    fn deref(&self) -> &Heap<i32, P> {
+        &self.heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_diverge_on_write() {
+        let mut base = CowHeap::new(Heap::new(2, &[3, 1, 4]).unwrap());
+        let mut branch = base.clone();
+        assert!(base.is_shared());
+
+        branch.insert(10).unwrap();
+        assert!(!base.is_shared());
+        assert!(!branch.is_shared());
+        assert_eq!(base.extract_max(), Ok(4));
+
+        assert_eq!(
+            base.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![3, 1]
+        );
+        assert_eq!(
+            branch.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![10, 4, 3, 1]
+        );
+    }
+
+    #[test]
+    fn test_failed_extract_keeps_sharing() {
+        let mut base = CowHeap::new(Heap::new(2, &[]).unwrap());
+        let other = base.clone();
+        assert_eq!(base.extract_max(), Err(HeapError::EmptyHeap));
+        assert!(other.is_shared());
+        assert_eq!(other.into_heap().len(), 0);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::aging::{Aged, Aging};
+use crate::calendar::CalendarQueue;
+use crate::handles::Handle;
+use crate::keyed::KeyedQueue;
+use crate::rng::Rng;
+use crate::timestamp;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Priority points a waiting patient gains per minute; ten minutes of waiting
+/// are worth one severity level.
+const AGING_PER_MINUTE: i64 = 1;
+const POINTS_PER_SEVERITY: i64 = 10;
+
+/// Options for the triage demo, parsed from the command line.
+struct TriageOptions {
+    seed: u64,
+    patients: u32,
+    doctors: usize,
+    step: bool,
+}
+
+#[derive(Clone, Copy)]
+struct Patient {
+    id: u32,
+    severity: u32,
+    arrived_at: u64,
+}
+
+enum Event {
+    Arrival(Patient),
+    Done { doctor: usize, patient: Patient },
+}
+
+/// State of the emergency room between events.
+struct Triage {
+    events: CalendarQueue<Event>,
+    waiting: KeyedQueue<Aged, Patient, Aging>,
+    free_doctors: Vec<usize>,
+    rng: Rng,
+    /// Total minutes waited and patients treated, per severity 1..=5.
+    waits: [(u64, u32); 5],
+}
+
+/// Ranks tasks by priority, highest first, then by the earliest due time.
+type TaskKey = (i64, Reverse<u64>);
+
+/// A queued task, with its due time as it was typed.
+struct Task {
+    name: String,
+    due: String,
+}
+
+/// Named tasks waiting to be done, for the tasks demo.
+#[derive(Default)]
+struct TaskList {
+    queue: KeyedQueue<TaskKey, Task>,
+    handles: HashMap<String, Handle>,
+}
+
+/// Runs a demo: `demo triage` or `demo tasks`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("triage") => run_triage(&args[1..]),
+        Some("tasks") => run_tasks(&args[1..]),
+        Some(other) => Err(format!("Unknown demo: {}", other)),
+        None => Err(
+            "Usage: demo triage [--seed N] [--patients N] [--doctors N] [--step] | demo tasks"
+                .to_string(),
+        ),
+    }
+}
+
+/// Parses `--seed N`, `--patients N`, `--doctors N` and `--step`.
+///
+/// # Edge cases
+/// * Without `--seed`, a seed is derived from the current time and printed
+/// * `--doctors 0` is rejected, since nobody would ever be treated
+fn parse_triage_options(args: &[String]) -> Result<TriageOptions, String> {
+    let mut options = TriageOptions {
+        seed: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_nanos() as u64)
+            .unwrap_or(0),
+        patients: 20,
+        doctors: 2,
+        step: false,
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--step" {
+            options.step = true;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag.as_str() {
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--patients" => options.patients = value.parse().map_err(|_| invalid())?,
+            "--doctors" => match value.parse::<usize>() {
+                Ok(doctors) if doctors > 0 => options.doctors = doctors,
+                _ => return Err(invalid()),
+            },
+            _ => return Err(format!("Unknown option: {}", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Simulates an emergency room: patients arrive at random with a severity
+/// from 1 to 5, the next free doctor takes whoever ranks highest in the
+/// waiting room, and waiting slowly raises a patient's rank so mild cases
+/// are not left behind forever.
+///
+/// Arrivals and treatment ends are events in a CalendarQueue keyed by the
+/// minute they happen; the waiting room is a KeyedQueue under the Aging
+/// policy.
+fn run_triage(args: &[String]) -> Result<(), String> {
+    let options = parse_triage_options(args)?;
+    println!(
+        "Triage: seed={} patients={} doctors={}",
+        options.seed, options.patients, options.doctors
+    );
+
+    let mut triage = Triage {
+        events: CalendarQueue::new(),
+        waiting: KeyedQueue::with_priority(Aging::new(AGING_PER_MINUTE)),
+        free_doctors: (1..=options.doctors).rev().collect(),
+        rng: Rng::new(options.seed),
+        waits: [(0, 0); 5],
+    };
+
+    let mut arrival = 0;
+    for id in 1..=options.patients {
+        arrival += 1 + triage.rng.below(8);
+        let severity = 1 + triage.rng.below(5) as u32;
+        triage.events.push(
+            arrival,
+            Event::Arrival(Patient {
+                id,
+                severity,
+                arrived_at: arrival,
+            }),
+        );
+    }
+
+    let stdin = io::stdin();
+    while let Some((now, event)) = triage.events.pop() {
+        triage.handle(now, event);
+        if options.step {
+            println!("  (press Enter for the next event)");
+            let mut line = String::new();
+            stdin
+                .lock()
+                .read_line(&mut line)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    println!("Average wait by severity:");
+    for (severity, &(total, count)) in triage.waits.iter().enumerate().rev() {
+        if count > 0 {
+            println!(
+                "  severity {}: {:.1} min over {} patients",
+                severity + 1,
+                total as f64 / count as f64,
+                count
+            );
+        }
+    }
+    Ok(())
+}
+
+impl Triage {
+    fn handle(&mut self, now: u64, event: Event) {
+        match event {
+            Event::Arrival(patient) => {
+                println!(
+                    "[{:>4} min] patient {} arrives with severity {}",
+                    now, patient.id, patient.severity
+                );
+                let aged = Aged {
+                    priority: patient.severity as i64 * POINTS_PER_SEVERITY,
+                    pushed_at: now,
+                };
+                self.waiting.push(aged, patient);
+            }
+            Event::Done { doctor, patient } => {
+                println!(
+                    "[{:>4} min] doctor {} finishes with patient {}",
+                    now, doctor, patient.id
+                );
+                self.free_doctors.push(doctor);
+            }
+        }
+
+        while let Some(&doctor) = self.free_doctors.last() {
+            let Some((_, patient)) = self.waiting.pop() else {
+                break;
+            };
+            self.free_doctors.pop();
+
+            let waited = now - patient.arrived_at;
+            let slot = &mut self.waits[patient.severity as usize - 1];
+            slot.0 += waited;
+            slot.1 += 1;
+
+            let duration = 5 + self.rng.below(5 * patient.severity as u64);
+            println!(
+                "[{:>4} min] doctor {} takes patient {} (severity {}, waited {} min), {} waiting",
+                now,
+                doctor,
+                patient.id,
+                patient.severity,
+                waited,
+                self.waiting.len()
+            );
+            self.events
+                .push(now + duration, Event::Done { doctor, patient });
+        }
+    }
+}
+
+/// A small task manager: reads `add <name> <priority> <due>`,
+/// `complete <name>` and `list-next [n]` commands from stdin, one per line,
+/// until EOF or `quit`.
+///
+/// A due time is a plain number or, built with the chrono or time feature,
+/// an RFC 3339 timestamp such as `2026-10-14T17:00:00+02:00`. Tasks live in
+/// a KeyedQueue ranked by priority, then by due time, and complete removes
+/// a task through the handle push returned for it.
+fn run_tasks(args: &[String]) -> Result<(), String> {
+    if !args.is_empty() {
+        return Err("Usage: demo tasks".to_string());
+    }
+    println!("Tasks: add <name> <priority> <due>, complete <name>, list-next [n], quit");
+
+    let mut tasks = TaskList::default();
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        match line.trim() {
+            "" => continue,
+            "quit" => break,
+            command => match tasks.execute(command) {
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("Error: {}", e),
+            },
+        }
+    }
+    Ok(())
+}
+
+impl TaskList {
+    /// Runs one command line and returns what to print.
+    ///
+    /// # Edge cases
+    /// * Adding a name that is already queued is refused, since complete
+    ///   picks tasks by name
+    /// * `list-next` without a count shows the next 3 tasks
+    /// * Timestamps are compared as nanoseconds since 1970 shifted by 2^63,
+    ///   so they all fall after every plain number a task is likely to use
+    fn execute(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |text: &str| {
+            text.parse::<u64>()
+                .map_err(|_| format!("{}: invalid number {}", words[0], text))
+        };
+
+        match words.as_slice() {
+            ["add", name, priority, due] => {
+                let priority = priority
+                    .parse::<i64>()
+                    .map_err(|_| format!("add: invalid priority {}", priority))?;
+                let ticks = match due.parse::<u64>() {
+                    Ok(ticks) => ticks,
+                    Err(_) => timestamp::parse_rfc3339(due).map_err(|e| format!("add: {}", e))?,
+                };
+                if self.handles.contains_key(*name) {
+                    return Err(format!("task {} already exists", name));
+                }
+                let task = Task {
+                    name: name.to_string(),
+                    due: due.to_string(),
+                };
+                let handle = self.queue.push((priority, Reverse(ticks)), task);
+                self.handles.insert(name.to_string(), handle);
+                Ok(format!(
+                    "added {} (priority {}, due {}), {} open",
+                    name,
+                    priority,
+                    due,
+                    self.queue.len()
+                ))
+            }
+            ["complete", name] => {
+                let handle = self
+                    .handles
+                    .remove(*name)
+                    .ok_or_else(|| format!("no open task {}", name))?;
+                match self.queue.remove(handle) {
+                    Some(_) => Ok(format!("completed {}, {} open", name, self.queue.len())),
+                    None => Err(format!("no open task {}", name)),
+                }
+            }
+            ["list-next", rest @ ..] if rest.len() <= 1 => {
+                let count = match rest.first() {
+                    Some(count) => number(count)? as usize,
+                    None => 3,
+                };
+                if self.queue.is_empty() {
+                    return Ok("nothing to do".to_string());
+                }
+                let lines: Vec<String> = self
+                    .queue
+                    .sorted_iter()
+                    .take(count)
+                    .enumerate()
+                    .map(|(n, ((priority, _), task))| {
+                        format!(
+                            "{}. {} (priority {}, due {})",
+                            n + 1,
+                            task.name,
+                            priority,
+                            task.due
+                        )
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            [name @ ("add" | "complete" | "list-next"), ..] => {
+                Err(format!("{}: wrong number of arguments", name))
+            }
+            [other, ..] => Err(format!("unknown command {}", other)),
+            [] => Err("empty command".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_list() {
+        let mut tasks = TaskList::default();
+        for line in [
+            "add report 2 50",
+            "add taxes 5 90",
+            "add email 2 10",
+            "add backup 1 5",
+        ] {
+            assert!(tasks.execute(line).is_ok());
+        }
+        assert_eq!(
+            tasks.execute("list-next"),
+            Ok("1. taxes (priority 5, due 90)\n\
+                2. email (priority 2, due 10)\n\
+                3. report (priority 2, due 50)"
+                .to_string())
+        );
+
+        assert_eq!(
+            tasks.execute("complete taxes"),
+            Ok("completed taxes, 3 open".to_string())
+        );
+        assert_eq!(
+            tasks.execute("complete taxes"),
+            Err("no open task taxes".to_string())
+        );
+        assert_eq!(
+            tasks.execute("add email 9 1"),
+            Err("task email already exists".to_string())
+        );
+        assert_eq!(
+            tasks.execute("list-next 1"),
+            Ok("1. email (priority 2, due 10)".to_string())
+        );
+        assert!(tasks.execute("add x high 3").is_err());
+        assert!(tasks.execute("list-next 1 2").is_err());
+        assert!(tasks.execute("finish x").is_err());
+    }
+
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    #[test]
+    fn test_task_due_timestamps() {
+        let mut tasks = TaskList::default();
+        for line in [
+            "add review 1 2026-10-14T18:00:00+02:00",
+            "add deploy 1 2026-10-14T15:30:00Z",
+            "add lunch 1 2026-10-14T12:00:00-05:00",
+        ] {
+            assert!(tasks.execute(line).is_ok());
+        }
+        // 16:00Z, 15:30Z and 17:00Z once the offsets are applied
+        assert_eq!(
+            tasks.execute("list-next"),
+            Ok("1. deploy (priority 1, due 2026-10-14T15:30:00Z)\n\
+                2. review (priority 1, due 2026-10-14T18:00:00+02:00)\n\
+                3. lunch (priority 1, due 2026-10-14T12:00:00-05:00)"
+                .to_string())
+        );
+        assert_eq!(
+            tasks.execute("add late 1 next-week"),
+            Err("add: invalid RFC 3339 timestamp next-week".to_string())
+        );
+    }
+}
@@ -0,0 +1,218 @@
+/// A small integer expression over a single variable `key`, for custom heap
+/// orderings such as `key % 10` or `abs(key - 50)`.
+///
+/// Grammar, with the usual precedence and left associativity:
+///
+/// ```text
+/// expr   := term (('+' | '-') term)*
+/// term   := factor (('*' | '/' | '%') factor)*
+/// factor := '-' factor | number | 'key' | 'abs' '(' expr ')' | '(' expr ')'
+/// ```
+///
+/// Evaluation uses i64 with wrapping arithmetic. `/` and `%` truncate as in
+/// Rust, and dividing by zero yields 0 so every key is defined.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Key,
+    Number(i64),
+    Neg(Box<Expr>),
+    Abs(Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Expr {
+    /// Parses an expression, returning a message describing the first error.
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {:?} in expression", token)),
+        }
+    }
+
+    /// Evaluates the expression with `key` bound to the given value.
+    pub fn eval(&self, key: i64) -> i64 {
+        match self {
+            Expr::Key => key,
+            Expr::Number(n) => *n,
+            Expr::Neg(e) => e.eval(key).wrapping_neg(),
+            Expr::Abs(e) => e.eval(key).wrapping_abs(),
+            Expr::Binary(lhs, op, rhs) => {
+                let (a, b) = (lhs.eval(key), rhs.eval(key));
+                match op {
+                    BinaryOp::Add => a.wrapping_add(b),
+                    BinaryOp::Sub => a.wrapping_sub(b),
+                    BinaryOp::Mul => a.wrapping_mul(b),
+                    BinaryOp::Div => a.checked_div(b).unwrap_or(0),
+                    BinaryOp::Rem => a.checked_rem(b).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Symbol(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                digits.push(d);
+                chars.next();
+            }
+            let n = digits
+                .parse()
+                .map_err(|_| format!("Number too large: {}", digits))?;
+            tokens.push(Token::Number(n));
+        } else if c.is_ascii_alphabetic() {
+            let mut ident = String::new();
+            while let Some(&a) = chars.peek().filter(|a| a.is_ascii_alphanumeric()) {
+                ident.push(a);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else if "+-*/%()".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(format!("Unexpected character '{}' in expression", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Consumes the next token if it is the given symbol.
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.tokens.get(self.pos) == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in expression", symbol))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.factor()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else if self.eat('%') {
+                BinaryOp::Rem
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.factor()?)));
+        }
+        if self.eat('(') {
+            let inner = self.expr()?;
+            self.expect(')')?;
+            return Ok(inner);
+        }
+
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "Unexpected end of expression".to_string())?;
+        self.pos += 1;
+        match token {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::Ident(name) if name == "key" => Ok(Expr::Key),
+            Token::Ident(name) if name == "abs" => {
+                self.expect('(')?;
+                let inner = self.expr()?;
+                self.expect(')')?;
+                Ok(Expr::Abs(Box::new(inner)))
+            }
+            Token::Ident(name) => Err(format!("Unknown name '{}' in expression", name)),
+            Token::Symbol(c) => Err(format!("Unexpected '{}' in expression", c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let eval = |text: &str, key: i64| Expr::parse(text).unwrap().eval(key);
+        assert_eq!(eval("key % 10", 1234), 4);
+        assert_eq!(eval("key + 2 * 3", 1), 7);
+        assert_eq!(eval("(key + 2) * 3", 1), 9);
+        assert_eq!(eval("10 - key - 3", 2), 5);
+        assert_eq!(eval("-key", 5), -5);
+        assert_eq!(eval("abs(key - 50)", 20), 30);
+        assert_eq!(eval("key / 0", 7), 0);
+        assert_eq!(eval("key % 0", 7), 0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expr::parse("").is_err());
+        assert!(Expr::parse("key +").is_err());
+        assert!(Expr::parse("(key").is_err());
+        assert!(Expr::parse("value % 10").is_err());
+        assert!(Expr::parse("key ^ 2").is_err());
+        assert!(Expr::parse("key 2").is_err());
+    }
+}
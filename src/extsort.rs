@@ -0,0 +1,259 @@
+use crate::heap::{Heap, HEAP_MAX_SIZE};
+use crate::merge::kmerge;
+use crate::priority::MinOrder;
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Branching factor of the heap used to sort each run.
+const RUN_D: u32 = 4;
+
+/// Maximum number of runs merged at once, to stay well below open-file limits.
+const MERGE_FAN_IN: usize = 64;
+
+/// Result of sorting a file with `sort_file`.
+pub struct ExtSortStats {
+    pub numbers: usize,
+    pub runs: usize,
+}
+
+/// Distinguishes scratch directories created by the same process.
+static SCRATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Scratch directory for run files, removed when dropped.
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    fn create() -> Result<Self, String> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_nanos())
+            .unwrap_or(0);
+        let path = env::temp_dir().join(format!(
+            "d-heap-extsort-{}-{}-{}",
+            process::id(),
+            nanos,
+            SCRATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(Self { path })
+    }
+
+    fn run_path(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Streams the numbers of a run file, one per line. Since the merge consumes
+/// plain iterators of i32, a read or parse failure ends the stream and is
+/// recorded in the shared error slot for the caller to check afterwards.
+struct RunReader {
+    lines: Lines<BufReader<File>>,
+    error: Rc<RefCell<Option<String>>>,
+}
+
+impl Iterator for RunReader {
+    type Item = i32;
+
+    fn next(&mut self) -> Option<i32> {
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => {
+                *self.error.borrow_mut() = Some(format!("Failed to read run: {}", e));
+                return None;
+            }
+        };
+        match line.trim().parse() {
+            Ok(x) => Some(x),
+            Err(_) => {
+                *self.error.borrow_mut() = Some(format!("Corrupt run line: {}", line));
+                None
+            }
+        }
+    }
+}
+
+/// Drains the min-heap into a new run file in ascending order.
+fn write_run(heap: &mut Heap<i32, MinOrder>, path: &Path) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    while let Ok(x) = heap.extract_max() {
+        writeln!(writer, "{}", x).map_err(|e| format!("Failed to write run: {}", e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write run: {}", e))
+}
+
+/// Splits the input into sorted runs of up to HEAP_MAX_SIZE numbers each.
+fn write_runs(input: &Path, scratch: &ScratchDir) -> Result<(Vec<PathBuf>, usize), String> {
+    let file =
+        File::open(input).map_err(|e| format!("Failed to open {}: {}", input.display(), e))?;
+    let mut heap = Heap::with_priority(RUN_D, &[], MinOrder).map_err(|e| e.to_string())?;
+    let mut runs = Vec::new();
+    let mut numbers = 0;
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", input.display(), e))?;
+        for token in line.split_whitespace() {
+            let x: i32 = token
+                .parse()
+                .map_err(|_| format!("Invalid number in input: {}", token))?;
+            if heap.len() == HEAP_MAX_SIZE {
+                let path = scratch.run_path(&format!("run-{}", runs.len()));
+                write_run(&mut heap, &path)?;
+                runs.push(path);
+            }
+            heap.insert(x).map_err(|e| e.to_string())?;
+            numbers += 1;
+        }
+    }
+
+    if !heap.is_empty() {
+        let path = scratch.run_path(&format!("run-{}", runs.len()));
+        write_run(&mut heap, &path)?;
+        runs.push(path);
+    }
+
+    Ok((runs, numbers))
+}
+
+/// Merges the given run files into a single sorted file at `output`.
+fn merge_runs(runs: &[PathBuf], output: &Path) -> Result<(), String> {
+    let error = Rc::new(RefCell::new(None));
+    let readers = runs
+        .iter()
+        .map(|path| {
+            let file = File::open(path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            Ok(RunReader {
+                lines: BufReader::new(file).lines(),
+                error: Rc::clone(&error),
+            })
+        })
+        .collect::<Result<Vec<RunReader>, String>>()?;
+
+    let file = File::create(output)
+        .map_err(|e| format!("Failed to create {}: {}", output.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    for x in kmerge(readers) {
+        writeln!(writer, "{}", x).map_err(|e| format!("Failed to write output: {}", e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write output: {}", e))?;
+
+    let error = error.borrow_mut().take();
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Sorts the whitespace-separated numbers of `input` into `output`, one per line,
+/// without holding more than one run of HEAP_MAX_SIZE numbers in memory.
+///
+/// Runs are merged MERGE_FAN_IN at a time, in as many passes as needed.
+pub fn sort_file(input: &Path, output: &Path) -> Result<ExtSortStats, String> {
+    let scratch = ScratchDir::create()?;
+    let (mut runs, numbers) = write_runs(input, &scratch)?;
+    let stats = ExtSortStats {
+        numbers,
+        runs: runs.len(),
+    };
+
+    let mut pass = 0;
+    while runs.len() > MERGE_FAN_IN {
+        let mut merged = Vec::new();
+        for (i, group) in runs.chunks(MERGE_FAN_IN).enumerate() {
+            let path = scratch.run_path(&format!("pass-{}-run-{}", pass, i));
+            merge_runs(group, &path)?;
+            merged.push(path);
+        }
+        for path in &runs {
+            let _ = fs::remove_file(path);
+        }
+        runs = merged;
+        pass += 1;
+    }
+
+    merge_runs(&runs, output)?;
+    Ok(stats)
+}
+
+/// Handles `extsort <input> <output>`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (input, output) = match args {
+        [input, output] => (input, output),
+        _ => return Err("Usage: extsort <input> <output>".to_string()),
+    };
+
+    let stats = sort_file(Path::new(input), Path::new(output))?;
+    println!(
+        "Sorted {} numbers from {} runs into {}",
+        stats.numbers, stats.runs, output
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_sort_file_multiple_passes() {
+        let scratch = ScratchDir::create().unwrap();
+        let input = scratch.run_path("input");
+        let output = scratch.run_path("output");
+
+        // Enough numbers for more than MERGE_FAN_IN runs, forcing a second pass
+        let mut rng = Rng::new(696);
+        let count = HEAP_MAX_SIZE * (MERGE_FAN_IN + 3) + 17;
+        let mut values: Vec<i32> = (0..count).map(|_| rng.range_i32(-5000, 5000)).collect();
+        let text: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+        fs::write(&input, text.join(" ")).unwrap();
+
+        let stats = sort_file(&input, &output).unwrap();
+        assert_eq!(stats.numbers, count);
+        assert_eq!(stats.runs, MERGE_FAN_IN + 4);
+
+        let sorted: Vec<i32> = fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        values.sort_unstable();
+        assert_eq!(sorted, values);
+    }
+
+    #[test]
+    fn test_sort_file_empty_and_invalid() {
+        let scratch = ScratchDir::create().unwrap();
+        let input = scratch.run_path("input");
+        let output = scratch.run_path("output");
+
+        fs::write(&input, "").unwrap();
+        assert_eq!(sort_file(&input, &output).unwrap().numbers, 0);
+        assert_eq!(fs::read_to_string(&output).unwrap(), "");
+
+        fs::write(&input, "3 x 1").unwrap();
+        assert!(sort_file(&input, &output).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::keyed::KeyedQueue;
+use crate::priority::MinOrder;
+
+/// Fixed-point scale for virtual times, so cost / weight keeps some fraction.
+const VIRTUAL_SCALE: u128 = 1 << 16;
+
+/// Identifies a class added to a FairQueue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassId(usize);
+
+struct Class {
+    weight: u64,
+    last_finish: u128,
+}
+
+/// A weighted fair queue: backlogged classes are served in proportion to
+/// their weights, measured in the cost of the items they send.
+///
+/// Uses self-clocked fair queueing. Each item gets a virtual finish time of
+/// `max(virtual now, class's previous finish) + cost / weight`, items come
+/// out of a KeyedQueue min-heap of finish times, earliest arrival first on
+/// ties, and virtual now advances to the finish
+/// time of each item popped. A class that was idle starts from virtual now,
+/// so it cannot bank credit while it has nothing queued.
+pub struct FairQueue<T> {
+    classes: Vec<Class>,
+    virtual_now: u128,
+    finishes: KeyedQueue<u128, (ClassId, T), MinOrder>,
+}
+
+impl<T> Default for FairQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FairQueue<T> {
+    /// Creates a queue with no classes.
+    pub fn new() -> Self {
+        Self {
+            classes: Vec::new(),
+            virtual_now: 0,
+            finishes: KeyedQueue::with_priority(MinOrder),
+        }
+    }
+
+    /// Adds a class with the given share weight.
+    ///
+    /// # Edge cases
+    /// * A weight of 0 is treated as 1, so every class is eventually served
+    pub fn add_class(&mut self, weight: u64) -> ClassId {
+        self.classes.push(Class {
+            weight: weight.max(1),
+            last_finish: 0,
+        });
+        ClassId(self.classes.len() - 1)
+    }
+
+    /// Queues an item for `class` that costs `cost` units of service.
+    pub fn push(&mut self, class: ClassId, cost: u64, item: T) {
+        let entry = &mut self.classes[class.0];
+        let start = self.virtual_now.max(entry.last_finish);
+        let finish = start + cost as u128 * VIRTUAL_SCALE / entry.weight as u128;
+        entry.last_finish = finish;
+        self.finishes.push(finish, (class, item));
+    }
+
+    /// Removes the item with the earliest virtual finish time.
+    pub fn pop(&mut self) -> Option<(ClassId, T)> {
+        let (finish, entry) = self.finishes.pop()?;
+        self.virtual_now = finish;
+        Some(entry)
+    }
+
+    /// Returns the number of queued items across all classes.
+    pub fn len(&self) -> usize {
+        self.finishes.len()
+    }
+
+    /// Returns true if no class has anything queued.
+    pub fn is_empty(&self) -> bool {
+        self.finishes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shares_follow_weights() {
+        let mut queue = FairQueue::new();
+        let heavy = queue.add_class(3);
+        let light = queue.add_class(1);
+        for i in 0..100 {
+            queue.push(heavy, 10, i);
+            queue.push(light, 10, i);
+        }
+
+        let first: Vec<ClassId> = (0..40)
+            .filter_map(|_| queue.pop())
+            .map(|(c, _)| c)
+            .collect();
+        assert_eq!(first.iter().filter(|&&c| c == heavy).count(), 30);
+        assert_eq!(first.iter().filter(|&&c| c == light).count(), 10);
+        assert_eq!(queue.len(), 160);
+    }
+
+    #[test]
+    fn test_cost_and_idle_classes() {
+        let mut queue = FairQueue::new();
+        let bulk = queue.add_class(1);
+        let small = queue.add_class(1);
+        // One big item costs as much as four small ones, so it ties with the
+        // fourth and wins the tie by arriving first
+        queue.push(bulk, 40, "big");
+        for name in ["s1", "s2", "s3", "s4", "s5"] {
+            queue.push(small, 10, name);
+        }
+        let order: Vec<&str> = std::iter::from_fn(|| queue.pop().map(|(_, x)| x)).collect();
+        assert_eq!(order, vec!["s1", "s2", "s3", "big", "s4", "s5"]);
+
+        // Having been idle earns a class no credit
+        let late = queue.add_class(1);
+        queue.push(small, 10, "a");
+        queue.push(late, 10, "b");
+        assert_eq!(queue.pop().map(|(c, _)| c), Some(small));
+        assert_eq!(queue.pop().map(|(c, _)| c), Some(late));
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Stable reference to an element of a Heap, returned by insert. It keeps
+/// pointing at the same element however the heap reorders itself, and goes
+/// stale once that element leaves the heap.
+///
+/// Shown as `slot.generation`: the slot is reused after its element leaves,
+/// with the generation bumped so the old handle no longer matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Handle {
+    slot: u32,
+    generation: u32,
+}
+
+impl Handle {
+    /// Returns the table slot the handle names.
+    pub fn slot(&self) -> u32 {
+        self.slot
+    }
+
+    /// Returns how many times the slot had been reused when it was issued.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.slot, self.generation)
+    }
+}
+
+impl FromStr for Handle {
+    type Err = String;
+
+    /// Parses the `slot.generation` form Display prints.
+    fn from_str(text: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid handle {}, expected slot.generation", text);
+        let (slot, generation) = text.split_once('.').ok_or_else(invalid)?;
+        Ok(Handle {
+            slot: slot.parse().map_err(|_| invalid())?,
+            generation: generation.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// The heap position of each live handle. Slots of elements that left the
+/// heap go on a free list and are handed out again with the next generation,
+/// so the table never outgrows the most elements the heap held at once.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HandleTable {
+    entries: Vec<Entry>,
+    free: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    generation: u32,
+    position: Option<usize>,
+}
+
+impl HandleTable {
+    /// Issues a handle for an element stored at `position`.
+    pub(crate) fn issue(&mut self, position: usize) -> Handle {
+        match self.free.pop() {
+            Some(slot) => {
+                let entry = &mut self.entries[slot as usize];
+                entry.position = Some(position);
+                Handle {
+                    slot,
+                    generation: entry.generation,
+                }
+            }
+            None => {
+                self.entries.push(Entry {
+                    generation: 0,
+                    position: Some(position),
+                });
+                Handle {
+                    slot: (self.entries.len() - 1) as u32,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Forgets a handle whose element left the heap, freeing its slot.
+    pub(crate) fn release(&mut self, handle: Handle) {
+        let entry = &mut self.entries[handle.slot as usize];
+        entry.position = None;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free.push(handle.slot);
+    }
+
+    /// Records that the handle's element now sits at `position`.
+    pub(crate) fn moved(&mut self, handle: Handle, position: usize) {
+        self.entries[handle.slot as usize].position = Some(position);
+    }
+
+    /// Returns where the handle's element is, or None if it is stale.
+    pub(crate) fn position(&self, handle: Handle) -> Option<usize> {
+        self.entries
+            .get(handle.slot as usize)
+            .filter(|entry| entry.generation == handle.generation)
+            .and_then(|entry| entry.position)
+    }
+
+    /// Returns true if the handle was issued by this table and its element
+    /// has since left, as opposed to a handle the table never issued.
+    pub(crate) fn is_stale(&self, handle: Handle) -> bool {
+        self.entries
+            .get(handle.slot as usize)
+            .is_some_and(|entry| handle.generation < entry.generation)
+    }
+
+    /// Returns the bytes the table has allocated for its entries and free list.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.entries.capacity() * std::mem::size_of::<Entry>()
+            + self.free.capacity() * std::mem::size_of::<u32>()
+    }
+
+    /// Returns how many slots the table has ever needed.
+    #[cfg(test)]
+    pub(crate) fn slots(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_are_reused_with_a_new_generation() {
+        let mut table = HandleTable::default();
+        let first = table.issue(0);
+        let second = table.issue(1);
+        table.release(first);
+        assert_eq!(table.position(first), None);
+        assert!(table.is_stale(first));
+
+        let reused = table.issue(4);
+        assert_eq!(reused.to_string(), "0.1");
+        assert_eq!(table.position(reused), Some(4));
+        assert_eq!(table.position(first), None);
+        table.moved(second, 7);
+        assert_eq!(table.position(second), Some(7));
+        assert_eq!(table.slots(), 2);
+
+        // Neither a slot past the table nor a future generation was issued
+        assert!(!table.is_stale("9.0".parse().unwrap()));
+        assert!(!table.is_stale("0.7".parse().unwrap()));
+        assert!(!table.is_stale(second));
+    }
+
+    #[test]
+    fn test_parse_handle() {
+        let handle: Handle = "3.2".parse().unwrap();
+        assert_eq!(handle.to_string(), "3.2");
+        assert!("3".parse::<Handle>().is_err());
+        assert!("a.1".parse::<Handle>().is_err());
+    }
+}
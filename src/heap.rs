@@ -1,20 +1,221 @@
+use crate::handles::{Handle, HandleTable};
+use crate::priority::{MaxOrder, Priority, ValueOrder};
+use std::collections::{BTreeSet, BinaryHeap};
+use std::fmt;
+use std::io;
+use std::mem;
 use std::result::Result;
 
-const HEAP_MAX_SIZE: usize = 1000;
-pub struct Heap {
-    array: [i32; HEAP_MAX_SIZE],
-    size: usize,
+/// Capacity of the menu's heap, and of any heap that wants the original
+/// fixed-size limit back through Heap::bounded.
+pub const HEAP_MAX_SIZE: usize = 1000;
+
+/// A d-ary heap of `T` ordered by `P`, stored in a Vec that grows as needed.
+/// With the default `MaxOrder` it is a max-heap; `Heap<T, MinOrder>` is a
+/// min-heap over the same code. Plain `Heap` is the i32 max-heap.
+///
+/// Every element gets a Handle when it enters the heap, which the key change
+/// and delete methods take instead of a slot index, since slots change with
+/// every sift.
+#[derive(Clone)]
+pub struct Heap<T = i32, P: Priority<T> = MaxOrder> {
+    items: Vec<T>,
+    handles: Vec<Handle>,
+    table: HandleTable,
+    bound: Option<usize>,
     d: u32,
+    sift_steps: u64,
+    priority: P,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum HeapError {
-    HeapFull,
+    HeapFull {
+        capacity: usize,
+    },
     EmptyHeap,
-    NoSuchParent,
+    NoSuchParent {
+        idx: usize,
+        parent_idx: usize,
+        size: usize,
+    },
     ParentReachedEnd,
-    SonReachedEnd,
-    InvalidSonIndex,
+    SonReachedEnd {
+        idx: usize,
+        n: u32,
+        size: usize,
+    },
+    InvalidSonIndex {
+        idx: usize,
+        n: u32,
+        d: u32,
+    },
+    InvalidD {
+        d: u32,
+    },
+    NotSortedDesc {
+        idx: usize,
+    },
+    NotAHeap {
+        idx: usize,
+    },
+    Overflow {
+        idx: usize,
+    },
+    InvalidScale {
+        k: i32,
+    },
+    IndexOutOfRange {
+        idx: usize,
+        size: usize,
+    },
+    UnknownHandle {
+        handle: Handle,
+    },
+    StaleHandle {
+        handle: Handle,
+    },
+    InvalidKeyChange {
+        handle: Handle,
+    },
+    Io {
+        kind: io::ErrorKind,
+    },
+    InvalidFile {
+        line: usize,
+    },
+    Inconsistent {
+        reason: &'static str,
+    },
+    WorkerStopped,
+}
+
+impl fmt::Display for HeapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeapError::HeapFull { capacity } => {
+                write!(f, "heap is full (capacity {})", capacity)
+            }
+            HeapError::EmptyHeap => write!(f, "heap is empty"),
+            HeapError::NoSuchParent {
+                idx,
+                parent_idx,
+                size,
+            } => write!(
+                f,
+                "parent {} of node {} is outside the heap (size {})",
+                parent_idx, idx, size
+            ),
+            HeapError::ParentReachedEnd => write!(f, "the root has no parent"),
+            HeapError::SonReachedEnd { idx, n, size } => write!(
+                f,
+                "son {} of node {} is outside the heap (size {})",
+                n, idx, size
+            ),
+            HeapError::InvalidSonIndex { idx, n, d } => {
+                write!(f, "node {} has no son number {} (d={})", idx, n, d)
+            }
+            HeapError::InvalidD { d } => write!(f, "d must be at least 1, got {}", d),
+            HeapError::NotSortedDesc { idx } => {
+                write!(
+                    f,
+                    "input is not sorted in descending order at index {}",
+                    idx
+                )
+            }
+            HeapError::NotAHeap { idx } => {
+                write!(f, "input violates the heap property at index {}", idx)
+            }
+            HeapError::Overflow { idx } => {
+                write!(f, "transform overflows i32 at index {}", idx)
+            }
+            HeapError::InvalidScale { k } => {
+                write!(f, "scale factor must be positive, got {}", k)
+            }
+            HeapError::IndexOutOfRange { idx, size } => {
+                write!(f, "index {} is outside the heap (size {})", idx, size)
+            }
+            HeapError::UnknownHandle { handle } => {
+                write!(f, "handle {} was never issued by this heap", handle)
+            }
+            HeapError::StaleHandle { handle } => {
+                write!(f, "handle {} is stale: its element left the heap", handle)
+            }
+            HeapError::InvalidKeyChange { handle } => {
+                write!(f, "the new key would move {} the wrong way", handle)
+            }
+            HeapError::Io { kind } => write!(f, "file error: {}", kind),
+            HeapError::InvalidFile { line } => {
+                write!(f, "file is not a saved heap (line {})", line)
+            }
+            HeapError::Inconsistent { reason } => {
+                write!(f, "internal state is inconsistent: {}", reason)
+            }
+            HeapError::WorkerStopped => write!(f, "the background worker has stopped"),
+        }
+    }
+}
+
+impl std::error::Error for HeapError {}
+
+impl From<io::Error> for HeapError {
+    fn from(e: io::Error) -> Self {
+        HeapError::Io { kind: e.kind() }
+    }
+}
+
+/// Stable numeric identifiers for HeapError variants, for callers that can
+/// only pass integers across a language boundary. 0 is left free to mean
+/// success, and existing values never change; new variants get new numbers.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    HeapFull = 1,
+    EmptyHeap = 2,
+    NoSuchParent = 3,
+    ParentReachedEnd = 4,
+    SonReachedEnd = 5,
+    InvalidSonIndex = 6,
+    InvalidD = 7,
+    NotSortedDesc = 8,
+    NotAHeap = 9,
+    Overflow = 10,
+    InvalidScale = 11,
+    IndexOutOfRange = 12,
+    UnknownHandle = 13,
+    InvalidKeyChange = 14,
+    Io = 15,
+    InvalidFile = 16,
+    Inconsistent = 17,
+    WorkerStopped = 18,
+    StaleHandle = 19,
+}
+
+impl HeapError {
+    /// Returns the stable numeric code for this error, dropping its fields.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            HeapError::HeapFull { .. } => ErrorCode::HeapFull,
+            HeapError::EmptyHeap => ErrorCode::EmptyHeap,
+            HeapError::NoSuchParent { .. } => ErrorCode::NoSuchParent,
+            HeapError::ParentReachedEnd => ErrorCode::ParentReachedEnd,
+            HeapError::SonReachedEnd { .. } => ErrorCode::SonReachedEnd,
+            HeapError::InvalidSonIndex { .. } => ErrorCode::InvalidSonIndex,
+            HeapError::InvalidD { .. } => ErrorCode::InvalidD,
+            HeapError::NotSortedDesc { .. } => ErrorCode::NotSortedDesc,
+            HeapError::NotAHeap { .. } => ErrorCode::NotAHeap,
+            HeapError::Overflow { .. } => ErrorCode::Overflow,
+            HeapError::InvalidScale { .. } => ErrorCode::InvalidScale,
+            HeapError::IndexOutOfRange { .. } => ErrorCode::IndexOutOfRange,
+            HeapError::UnknownHandle { .. } => ErrorCode::UnknownHandle,
+            HeapError::StaleHandle { .. } => ErrorCode::StaleHandle,
+            HeapError::InvalidKeyChange { .. } => ErrorCode::InvalidKeyChange,
+            HeapError::Io { .. } => ErrorCode::Io,
+            HeapError::InvalidFile { .. } => ErrorCode::InvalidFile,
+            HeapError::Inconsistent { .. } => ErrorCode::Inconsistent,
+            HeapError::WorkerStopped => ErrorCode::WorkerStopped,
+        }
+    }
 }
 
 impl Heap {
@@ -25,145 +226,713 @@ impl Heap {
     /// * `slice` - Initial values for the heap
     ///
     /// # Edge cases
-    /// * If slice exceeds MAX_SIZE, only the first MAX_SIZE elements are used
-    /// * Automatically builds a valid max-heap from the provided elements
-    pub fn new(d: u32, slice: &[i32]) -> Self {
-        let mut heap = Self {
-            array: [-1; HEAP_MAX_SIZE],
-            size: 0,
-            d,
-        };
+    /// * Returns InvalidD error if d is 0
+    /// * Automatically builds a valid max-heap from the provided elements,
+    ///   unless they already form one
+    pub fn new(d: u32, slice: &[i32]) -> Result<Self, HeapError> {
+        Self::with_priority(d, slice, MaxOrder)
+    }
+
+    /// Creates a new d-ary max-heap from a slice sorted in descending order,
+    /// skipping build_heap since such a slice is already a valid heap for any d.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * Returns NotSortedDesc error with the first out-of-order index otherwise
+    pub fn from_sorted_desc(d: u32, slice: &[i32]) -> Result<Self, HeapError> {
+        let heap = Self::from_raw(d, slice.to_vec(), MaxOrder)?;
+        match heap.items.windows(2).position(|w| w[0] < w[1]) {
+            Some(pos) => Err(HeapError::NotSortedDesc { idx: pos + 1 }),
+            None => Ok(heap),
+        }
+    }
 
-        let slice_len = std::cmp::min(slice.len(), HEAP_MAX_SIZE);
-        heap.array[..slice_len].copy_from_slice(&slice[..slice_len]);
-        heap.size = slice_len;
-        heap.build_heap();
-        heap
+    /// Creates a new d-ary max-heap from a slice that already satisfies the
+    /// max-heap property for the given d, skipping build_heap.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * Returns NotAHeap error with the first violating index otherwise
+    pub fn from_heapified(d: u32, slice: &[i32]) -> Result<Self, HeapError> {
+        let heap = Self::from_raw(d, slice.to_vec(), MaxOrder)?;
+        match heap.first_violation() {
+            Some(idx) => Err(HeapError::NotAHeap { idx }),
+            None => Ok(heap),
+        }
     }
+}
 
-    /// Inserts a new item into the heap and maintains the max-heap property.
+impl<T: Clone, P: Priority<T>> Heap<T, P> {
+    /// Creates a new d-ary heap from the given slice, ordered by `priority`.
     ///
     /// # Edge cases
-    /// * Returns HeapFull error if the heap has reached MAX_SIZE
+    /// * Returns InvalidD error if d is 0
+    /// * Skips build_heap when the slice already satisfies the heap property,
+    ///   which the O(n) check usually rules out within the first few nodes
+    pub fn with_priority(d: u32, slice: &[T], priority: P) -> Result<Self, HeapError> {
+        Self::from_vec(d, slice.to_vec(), priority)
+    }
+
+    /// Creates a heap that never holds more than `capacity` elements, as the
+    /// fixed-size array did: the buffer is allocated up front and insert
+    /// returns HeapFull instead of growing it.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * If slice exceeds `capacity`, only the first `capacity` elements are used
+    pub fn bounded(d: u32, capacity: usize, slice: &[T], priority: P) -> Result<Self, HeapError> {
+        let mut items = Vec::with_capacity(capacity);
+        items.extend_from_slice(&slice[..slice.len().min(capacity)]);
+        let mut heap = Self::from_vec(d, items, priority)?;
+        heap.handles.reserve_exact(capacity - heap.handles.len());
+        heap.bound = Some(capacity);
+        Ok(heap)
+    }
+
+    /// Creates a new d-ary heap from a slice the caller guarantees already
+    /// satisfies the heap property, skipping both the check and build_heap.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * If the slice isn't actually a heap, later operations return elements
+    ///   in the wrong order; use is_heap to detect this
+    pub fn from_slice_unchecked(d: u32, slice: &[T], priority: P) -> Result<Self, HeapError> {
+        Self::from_raw(d, slice.to_vec(), priority)
+    }
+}
+
+impl<T, P: Priority<T>> Heap<T, P> {
+    /// Creates a new d-ary heap from the items, ordered by `priority`. The
+    /// Vec's allocation becomes the heap's and is arranged in place.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * Skips build_heap when the items already satisfy the heap property
+    pub fn from_vec(d: u32, items: Vec<T>, priority: P) -> Result<Self, HeapError> {
+        let mut heap = Self::from_raw(d, items, priority)?;
+        if heap.first_violation().is_some() {
+            heap.build_heap()?;
+        }
+        heap.check_invariants("from_vec");
+        Ok(heap)
+    }
+
+    /// Inserts a new item into the heap and maintains the heap property,
+    /// returning the item's handle.
+    ///
+    /// # Edge cases
+    /// * Only fails for a heap made by bounded, with HeapFull once it holds
+    ///   its capacity
     /// * Maintains max-heap property using heapify_up
-    pub fn insert(&mut self, item: i32) -> std::result::Result<(), HeapError> {
-        if self.size >= HEAP_MAX_SIZE {
-            return Err(HeapError::HeapFull);
+    pub fn insert(&mut self, item: T) -> std::result::Result<Handle, HeapError> {
+        if let Some(capacity) = self.bound {
+            if self.items.len() >= capacity {
+                return Err(HeapError::HeapFull { capacity });
+            }
         }
 
-        self.array[self.size] = item;
-        self.size += 1;
-        return self.heapify_up(self.size - 1);
+        let idx = self.items.len();
+        let handle = self.table.issue(idx);
+        self.items.push(item);
+        self.handles.push(handle);
+        let result = self.heapify_up(idx);
+        self.check_invariants("insert");
+        result.map(|()| handle)
     }
 
-    /// Removes and returns the maximum element from the heap.
+    /// Removes and returns the maximum element from the heap, that is the one
+    /// with the highest priority under `P`.
     ///
     /// # Edge cases
     /// * Returns EmptyHeap error if heap is empty
-    pub fn extract_max(&mut self) -> Result<i32, HeapError> {
-        if self.size == 0 {
+    pub fn extract_max(&mut self) -> Result<T, HeapError> {
+        if self.items.is_empty() {
             Err(HeapError::EmptyHeap)
         } else {
-            let max = self.array[0];
-            self.array[0] = self.array[self.size - 1];
-            self.size -= 1;
-            self.heapify_down(0)?;
+            let max = self.take(0);
+            if !self.items.is_empty() {
+                self.heapify_down(0)?;
+            }
+            self.check_invariants("extract_max");
             Ok(max)
         }
     }
 
+    /// Returns the maximum element without removing it.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty
+    pub fn peek_max(&self) -> Result<&T, HeapError> {
+        self.items.first().ok_or(HeapError::EmptyHeap)
+    }
+
+    /// Replaces the maximum element with a new item and returns the old maximum,
+    /// with a single heapify_down instead of an extract followed by an insert.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty, without inserting the item
+    /// * The old maximum's handle goes stale and the item gets a new one,
+    ///   which handle_at(0) returns before anything else changes the heap
+    pub fn replace_max(&mut self, item: T) -> Result<T, HeapError> {
+        if self.items.is_empty() {
+            return Err(HeapError::EmptyHeap);
+        }
+
+        self.table.release(self.handles[0]);
+        self.handles[0] = self.table.issue(0);
+        let max = mem::replace(&mut self.items[0], item);
+        self.heapify_down(0)?;
+        self.check_invariants("replace_max");
+        Ok(max)
+    }
+
+    /// Removes and returns the element at `idx`, moving the last element into
+    /// its slot and sifting it whichever way restores the heap property.
+    ///
+    /// # Edge cases
+    /// * Returns IndexOutOfRange error if idx is not a slot in the heap
+    pub fn remove_at(&mut self, idx: usize) -> Result<T, HeapError> {
+        if idx >= self.items.len() {
+            return Err(HeapError::IndexOutOfRange {
+                idx,
+                size: self.items.len(),
+            });
+        }
+
+        let removed = self.take(idx);
+        if idx < self.items.len() {
+            self.heapify_up(idx)?;
+            self.heapify_down(idx)?;
+        }
+        self.check_invariants("remove_at");
+        Ok(removed)
+    }
+
+    /// Returns the element the handle refers to.
+    ///
+    /// # Edge cases
+    /// * Returns StaleHandle error if the element has left the heap, and
+    ///   UnknownHandle error if the handle was never issued by it
+    pub fn get(&self, handle: Handle) -> Result<&T, HeapError> {
+        self.position(handle).map(|idx| &self.items[idx])
+    }
+
+    /// Returns the slot the handle's element currently sits in.
+    ///
+    /// # Edge cases
+    /// * Returns StaleHandle error if the element has left the heap, and
+    ///   UnknownHandle error if the handle was never issued by it
+    pub fn position(&self, handle: Handle) -> Result<usize, HeapError> {
+        self.table.position(handle).ok_or_else(|| {
+            if self.table.is_stale(handle) {
+                HeapError::StaleHandle { handle }
+            } else {
+                HeapError::UnknownHandle { handle }
+            }
+        })
+    }
+
+    /// Returns the handle of the element in slot `idx`.
+    ///
+    /// # Edge cases
+    /// * Returns IndexOutOfRange error if idx is not a slot in the heap
+    pub fn handle_at(&self, idx: usize) -> Result<Handle, HeapError> {
+        self.handles
+            .get(idx)
+            .copied()
+            .ok_or(HeapError::IndexOutOfRange {
+                idx,
+                size: self.items.len(),
+            })
+    }
+
+    /// Returns the (handle, value) pairs in storage order.
+    pub fn handles(&self) -> impl Iterator<Item = (Handle, &T)> + '_ {
+        self.handles.iter().copied().zip(&self.items)
+    }
+
+    /// Replaces the handle's element with one of at least its priority and
+    /// sifts it up, returning the old value.
+    ///
+    /// # Edge cases
+    /// * Returns StaleHandle error if the element has left the heap, and
+    ///   UnknownHandle error if the handle was never issued by it
+    /// * Returns InvalidKeyChange error if `value` ranks below the old value,
+    ///   leaving the heap unchanged
+    pub fn increase_key(&mut self, handle: Handle, value: T) -> Result<T, HeapError> {
+        let (idx, old) = self.key_change(handle, value, false)?;
+        self.heapify_up(idx)?;
+        self.check_invariants("increase_key");
+        Ok(old)
+    }
+
+    /// Replaces the handle's element with one of at most its priority and
+    /// sifts it down, returning the old value.
+    ///
+    /// # Edge cases
+    /// * Returns StaleHandle error if the element has left the heap, and
+    ///   UnknownHandle error if the handle was never issued by it
+    /// * Returns InvalidKeyChange error if `value` ranks above the old value,
+    ///   leaving the heap unchanged
+    pub fn decrease_key(&mut self, handle: Handle, value: T) -> Result<T, HeapError> {
+        let (idx, old) = self.key_change(handle, value, true)?;
+        self.heapify_down(idx)?;
+        self.check_invariants("decrease_key");
+        Ok(old)
+    }
+
+    /// Removes and returns the handle's element; see remove_at.
+    ///
+    /// # Edge cases
+    /// * Returns StaleHandle error if the element has already left the heap,
+    ///   and UnknownHandle error if the handle was never issued by it
+    pub fn delete(&mut self, handle: Handle) -> Result<T, HeapError> {
+        let idx = self.position(handle)?;
+        self.remove_at(idx)
+    }
+
+    /// Returns true if `a` would be extracted before `b` under this heap's order.
+    pub fn outranks(&self, a: &T, b: &T) -> bool {
+        self.priority.higher_than(a, b)
+    }
+
+    /// Returns the (index, value) of an element with the lowest priority,
+    /// the one extract_max would return last. Only leaves are scanned, since
+    /// every internal node outranks or ties its sons.
+    pub fn lowest(&self) -> Option<(usize, &T)> {
+        self.leaves().reduce(|lowest, leaf| {
+            if self.priority.higher_than(lowest.1, leaf.1) {
+                leaf
+            } else {
+                lowest
+            }
+        })
+    }
+
+    /// Counts the nodes that outrank their parent and restores the heap
+    /// property, returning that count.
+    ///
+    /// Only the parents of violating nodes and their ancestors are sifted
+    /// down, deepest first: every other subtree is already a valid heap, so
+    /// this is build_heap restricted to the subtrees that need it.
+    pub fn repair(&mut self) -> Result<usize, HeapError> {
+        let violations: Vec<usize> = (1..self.items.len())
+            .filter(|&i| match self.get_parent(i) {
+                Ok(parent_idx) => self
+                    .priority
+                    .higher_than(&self.items[i], &self.items[parent_idx]),
+                Err(_) => false,
+            })
+            .collect();
+
+        let mut to_fix = BTreeSet::new();
+        for &idx in &violations {
+            let mut current = idx;
+            while let Ok(parent_idx) = self.get_parent(current) {
+                if !to_fix.insert(parent_idx) {
+                    break;
+                }
+                current = parent_idx;
+            }
+        }
+
+        for &idx in to_fix.iter().rev() {
+            self.heapify_down(idx)?;
+        }
+        self.check_invariants("repair");
+        Ok(violations.len())
+    }
+
     /// Changes the branching factor of the heap and rebuilds it.
     ///
-    pub fn change_d(&mut self, d: u32) {
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0, leaving the heap unchanged
+    pub fn change_d(&mut self, d: u32) -> Result<(), HeapError> {
+        if d == 0 {
+            return Err(HeapError::InvalidD { d });
+        }
+
         self.d = d;
-        self.build_heap();
+        let result = self.build_heap();
+        self.check_invariants("change_d");
+        result
     }
 
-    /// Prints a visual representation of the heap by levels.
-    pub fn print(&self) {
-        println!("Heap (d={})", self.d);
-        if self.size == 0 {
-            println!("Empty heap :(");
-            return;
+    /// Applies `f` to every element and rebuilds the heap once afterwards,
+    /// instead of paying a sift per element.
+    ///
+    /// # Edge cases
+    /// * `f` sees the elements in storage order, not in priority order
+    pub fn map_in_place(&mut self, f: impl FnMut(&mut T)) -> Result<(), HeapError> {
+        self.items.iter_mut().for_each(f);
+        let result = self.build_heap();
+        self.check_invariants("map_in_place");
+        result
+    }
+
+    /// Returns the number of elements currently in the heap.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true if the heap holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the branching factor of the heap.
+    pub fn d(&self) -> u32 {
+        self.d
+    }
+
+    /// Returns the most elements the heap can hold, or None if it grows freely.
+    pub fn capacity(&self) -> Option<usize> {
+        self.bound
+    }
+
+    /// Returns the bytes allocated outside the struct itself: the element
+    /// buffer, the handle of each slot and the handle table, counted by
+    /// capacity rather than length.
+    pub fn heap_bytes(&self) -> usize {
+        self.items.capacity() * mem::size_of::<T>()
+            + self.handles.capacity() * mem::size_of::<Handle>()
+            + self.table.heap_bytes()
+    }
+
+    /// Returns how many swaps heapify_up and heapify_down have made over the
+    /// heap's lifetime, so callers can diff it around an operation.
+    pub fn sift_steps(&self) -> u64 {
+        self.sift_steps
+    }
+
+    /// Checks that no node has a higher priority than its parent.
+    pub fn is_heap(&self) -> bool {
+        self.first_violation().is_none()
+    }
+
+    /// Returns the elements in storage order, where the sons of slot i are
+    /// at slots i*d + 1 through i*d + d.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Consumes the heap, returning its elements in storage order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Returns the (index, value) pairs of the nodes that have no sons.
+    pub fn leaves(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        (self.first_leaf()..self.items.len()).map(|i| (i, &self.items[i]))
+    }
+
+    /// Returns the (index, value) pairs of the nodes that have at least one son,
+    /// the ones build_heap sifts down.
+    pub fn internal_nodes(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        (0..self.first_leaf()).map(|i| (i, &self.items[i]))
+    }
+
+    /// Returns the (index, value) pairs from the node at `idx` up to the root,
+    /// both included. Along the path, each value never outranks the next one.
+    ///
+    /// # Edge cases
+    /// * Returns IndexOutOfRange error if idx is not a slot in the heap
+    pub fn path_to_root(&self, idx: usize) -> Result<Vec<(usize, &T)>, HeapError> {
+        if idx >= self.items.len() {
+            return Err(HeapError::IndexOutOfRange {
+                idx,
+                size: self.items.len(),
+            });
         }
 
-        let mut start = 0;
-        let mut count = 1;
-        let mut level = 0;
+        let mut path = vec![(idx, &self.items[idx])];
+        let mut current = idx;
+        loop {
+            match self.get_parent(current) {
+                Ok(parent_idx) => {
+                    path.push((parent_idx, &self.items[parent_idx]));
+                    current = parent_idx;
+                }
+                Err(HeapError::ParentReachedEnd) => return Ok(path),
+                Err(e) => return Err(e),
+            }
+        }
+    }
 
-        while start < self.size {
-            let end = std::cmp::min(self.size, start + count);
-            print!("Level {}: ", level);
-            for i in start..end {
-                print!("{} ", self.array[i]);
+    /// Returns the d sons of the node at `idx` as (index, value) pairs, with
+    /// None for each son slot past the end of the heap.
+    ///
+    /// # Edge cases
+    /// * Returns IndexOutOfRange error if idx is not a slot in the heap
+    pub fn children(&self, idx: usize) -> Result<Vec<Option<(usize, &T)>>, HeapError> {
+        if idx >= self.items.len() {
+            return Err(HeapError::IndexOutOfRange {
+                idx,
+                size: self.items.len(),
+            });
+        }
+
+        (0..self.d)
+            .map(|n| match self.get_n_son(idx, n) {
+                Ok(son_idx) => Ok(Some((son_idx, &self.items[son_idx]))),
+                Err(HeapError::SonReachedEnd { .. }) => Ok(None),
+                Err(e) => Err(e),
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the elements in extraction order, leaving the
+    /// heap itself untouched.
+    pub fn sorted_iter(&self) -> SortedIter<'_, T, P> {
+        let frontier = if self.items.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        SortedIter {
+            heap: self,
+            frontier,
+        }
+    }
+
+    /// Wraps the items in a new heap without arranging them.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    fn from_raw(d: u32, items: Vec<T>, priority: P) -> Result<Self, HeapError> {
+        if d == 0 {
+            return Err(HeapError::InvalidD { d });
+        }
+        Ok(Self::wrap(d, items, priority))
+    }
+
+    /// Wraps the items as they are, issuing their handles in input order.
+    fn wrap(d: u32, items: Vec<T>, priority: P) -> Self {
+        let mut table = HandleTable::default();
+        let handles = (0..items.len()).map(|idx| table.issue(idx)).collect();
+        Self {
+            items,
+            handles,
+            table,
+            bound: None,
+            d,
+            sift_steps: 0,
+            priority,
+        }
+    }
+
+    /// Checks a key change for the handle goes the requested way, then writes
+    /// the new value and returns its slot and the old value.
+    fn key_change(
+        &mut self,
+        handle: Handle,
+        value: T,
+        decrease: bool,
+    ) -> Result<(usize, T), HeapError> {
+        let idx = self.position(handle)?;
+        let old = &self.items[idx];
+        let wrong_way = if decrease {
+            self.priority.higher_than(&value, old)
+        } else {
+            self.priority.higher_than(old, &value)
+        };
+        if wrong_way {
+            return Err(HeapError::InvalidKeyChange { handle });
+        }
+        Ok((idx, mem::replace(&mut self.items[idx], value)))
+    }
+
+    /// Removes slot `idx` by moving the last element into it, retiring the
+    /// removed element's handle. The caller sifts the moved element.
+    fn take(&mut self, idx: usize) -> T {
+        let item = self.items.swap_remove(idx);
+        self.table.release(self.handles.swap_remove(idx));
+        if let Some(&moved) = self.handles.get(idx) {
+            self.table.moved(moved, idx);
+        }
+        item
+    }
+
+    /// With the debug-invariants feature, panics naming `op` if the heap
+    /// property or the size bound no longer holds. Without it, does nothing.
+    fn check_invariants(&self, op: &str) {
+        if cfg!(feature = "debug-invariants") {
+            if let Some(capacity) = self.bound {
+                assert!(
+                    self.items.len() <= capacity,
+                    "{} left size {} over capacity {}",
+                    op,
+                    self.items.len(),
+                    capacity
+                );
             }
-            println!();
-            start = end;
-            count *= self.d as usize;
-            level += 1;
+            if let Some(idx) = self.first_violation() {
+                panic!(
+                    "{} broke the heap property at index {} (d={})",
+                    op, idx, self.d
+                );
+            }
+            for (idx, &handle) in self.handles.iter().enumerate() {
+                assert_eq!(
+                    self.table.position(handle),
+                    Some(idx),
+                    "{} lost track of handle {}",
+                    op,
+                    handle
+                );
+            }
+        }
+    }
+
+    /// Returns the index of the first leaf. Every node before it has at least
+    /// one son, since the last internal node is the parent of the last slot.
+    fn first_leaf(&self) -> usize {
+        if self.items.len() < 2 {
+            0
+        } else {
+            (self.items.len() - 2) / (self.d as usize) + 1
         }
     }
 
+    /// Finds the first node with a higher priority than its parent, if any.
+    fn first_violation(&self) -> Option<usize> {
+        (1..self.items.len()).find(|&i| match self.get_parent(i) {
+            Ok(parent_idx) => self
+                .priority
+                .higher_than(&self.items[i], &self.items[parent_idx]),
+            Err(_) => true,
+        })
+    }
+
     /// Builds a max-heap from an unordered array by applying heapify_down
     /// on all the nodes that aren't leaves.
-    fn build_heap(&mut self) {
-        for i in (0..(self.size / 2)).rev() {
-            self.heapify_down(i).unwrap();
+    fn build_heap(&mut self) -> Result<(), HeapError> {
+        for i in (0..self.first_leaf()).rev() {
+            self.heapify_down(i)?;
         }
+        Ok(())
     }
 
     /// Restores max-heap property by moving element at given index down the heap.
+    ///
+    /// d = 2 and d = 4 are dispatched to sift_down_fixed, which compares the sons
+    /// in an unrolled tournament instead of looping over get_n_son.
     fn heapify_down(&mut self, idx: usize) -> Result<(), HeapError> {
+        match self.d {
+            2 => {
+                self.sift_down_fixed::<2>(idx);
+                Ok(())
+            }
+            4 => {
+                self.sift_down_fixed::<4>(idx);
+                Ok(())
+            }
+            _ => self.heapify_down_generic(idx),
+        }
+    }
+
+    /// Restores max-heap property by moving element at given index down the heap,
+    /// for any d.
+    fn heapify_down_generic(&mut self, idx: usize) -> Result<(), HeapError> {
         let mut largest_idx: usize = idx;
-        let mut largest_val: i32 = self.array[idx];
 
         for n_son in 0..self.d {
             match self.get_n_son(idx, n_son) {
                 Ok(son_idx) => {
-                    if self.array[son_idx] > largest_val {
+                    if self
+                        .priority
+                        .higher_than(&self.items[son_idx], &self.items[largest_idx])
+                    {
                         largest_idx = son_idx;
-                        largest_val = self.array[son_idx];
                     }
                 }
-                Err(HeapError::SonReachedEnd) => {}
+                Err(HeapError::SonReachedEnd { .. }) => {}
                 Err(x) => return Err(x),
             }
         }
 
         if largest_idx != idx {
             // We found a son with a bigger value, then exchange, bringing son up
-            self.array[largest_idx] = self.array[idx];
-            self.array[idx] = largest_val;
-            return self.heapify_down(largest_idx);
+            self.swap(largest_idx, idx);
+            return self.heapify_down_generic(largest_idx);
         }
 
         Ok(())
     }
 
+    /// Same as heapify_down_generic for d = 2 or d = 4 (given as D), comparing the
+    /// sons pairwise in a fixed tournament when all of them are present.
+    /// Picks the same son on ties: the node itself first, then the leftmost son.
+    fn sift_down_fixed<const D: usize>(&mut self, mut idx: usize) {
+        let size = self.items.len();
+        loop {
+            let first_son = idx * D + 1;
+            if first_son >= size {
+                return;
+            }
+
+            let son_idx = if first_son + D <= size {
+                match D {
+                    2 => self.larger_of(first_son, first_son + 1),
+                    _ => {
+                        let left = self.larger_of(first_son, first_son + 1);
+                        let right = self.larger_of(first_son + 2, first_son + 3);
+                        self.larger_of(left, right)
+                    }
+                }
+            } else {
+                // Only the last node with sons can have some of them missing
+                (first_son + 1..size).fold(first_son, |a, b| self.larger_of(a, b))
+            };
+
+            if !self
+                .priority
+                .higher_than(&self.items[son_idx], &self.items[idx])
+            {
+                return;
+            }
+            self.swap(son_idx, idx);
+            idx = son_idx;
+        }
+    }
+
+    /// Returns whichever of the two indices holds the higher priority value,
+    /// preferring `a` on ties.
+    fn larger_of(&self, a: usize, b: usize) -> usize {
+        if self.priority.higher_than(&self.items[b], &self.items[a]) {
+            b
+        } else {
+            a
+        }
+    }
+
     /// Restores max-heap property by moving element at given index up the heap.
     fn heapify_up(&mut self, idx: usize) -> Result<(), HeapError> {
-        let mut smallest_idx: usize = idx;
-        let mut smallest_val: i32 = self.array[idx];
-
         match self.get_parent(idx) {
             Ok(parent_idx) => {
-                if self.array[parent_idx] < smallest_val {
-                    smallest_idx = parent_idx;
-                    smallest_val = self.array[parent_idx];
+                if self
+                    .priority
+                    .higher_than(&self.items[idx], &self.items[parent_idx])
+                {
+                    // we found a parent with a smaller value, then exchange bringing parent down
+                    self.swap(parent_idx, idx);
+                    return self.heapify_up(parent_idx);
                 }
+                Ok(())
             }
-            Err(HeapError::ParentReachedEnd) => return Ok(()),
-            Err(x) => return Err(x),
-        }
-
-        if smallest_idx != idx {
-            // we found a parent with a smaller value, then exchange bringing parent down
-            self.array[smallest_idx] = self.array[idx];
-            self.array[idx] = smallest_val;
-            return self.heapify_up(smallest_idx);
+            Err(HeapError::ParentReachedEnd) => Ok(()),
+            Err(x) => Err(x),
         }
+    }
 
-        Ok(())
+    /// Exchanges two slots, counting the move as one sift step. Both
+    /// elements' handles are pointed at their new slots.
+    fn swap(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+        self.handles.swap(a, b);
+        self.table.moved(self.handles[a], a);
+        self.table.moved(self.handles[b], b);
+        self.sift_steps += 1;
     }
 
     /// Gets the parent index of a given node.
@@ -176,10 +945,14 @@ impl Heap {
             return Err(HeapError::ParentReachedEnd);
         }
         let parent_idx = (idx - 1) / self.d as usize;
-        if parent_idx < self.size {
+        if parent_idx < self.items.len() {
             Ok(parent_idx)
         } else {
-            Err(HeapError::NoSuchParent)
+            Err(HeapError::NoSuchParent {
+                idx,
+                parent_idx,
+                size: self.items.len(),
+            })
         }
     }
 
@@ -190,57 +963,235 @@ impl Heap {
     /// * Returns SonReachedEnd error if calculated son index is outside heap bounds
     fn get_n_son(&self, idx: usize, n: u32) -> Result<usize, HeapError> {
         if n >= self.d {
-            return Err(HeapError::InvalidSonIndex);
+            return Err(HeapError::InvalidSonIndex { idx, n, d: self.d });
         }
 
         let son_idx = idx * (self.d as usize) + (n as usize) + 1;
-        if son_idx < self.size {
+        if son_idx < self.items.len() {
             Ok(son_idx)
         } else {
-            Err(HeapError::SonReachedEnd)
+            Err(HeapError::SonReachedEnd {
+                idx,
+                n,
+                size: self.items.len(),
+            })
+        }
+    }
+}
+
+impl<T: fmt::Display, P: Priority<T>> Heap<T, P> {
+    /// Prints a visual representation of the heap by levels.
+    pub fn print(&self) {
+        println!("Heap (d={})", self.d);
+        if self.items.is_empty() {
+            println!("Empty heap :(");
+            return;
+        }
+
+        let mut start = 0;
+        let mut count = 1;
+        let mut level = 0;
+
+        while start < self.items.len() {
+            let end = std::cmp::min(self.items.len(), start + count);
+            print!("Level {}: ", level);
+            for item in &self.items[start..end] {
+                print!("{} ", item);
+            }
+            println!();
+            start = end;
+            count *= self.d as usize;
+            level += 1;
+        }
+    }
+
+    /// Describes the heap as one line of text, parent by parent, for output
+    /// that has to make sense when read aloud, e.g.
+    /// "heap with d=2 and 3 elements: root 20; children of 20: 10, 5".
+    pub fn describe_plain(&self) -> String {
+        let mut text = format!("heap with d={} and {} elements", self.d, self.items.len());
+        if self.items.is_empty() {
+            return text;
+        }
+
+        text += &format!(": root {}", self.items[0]);
+        for idx in 0..self.items.len() {
+            let sons: Vec<String> = (0..self.d)
+                .map_while(|n| self.get_n_son(idx, n).ok())
+                .map(|son| self.items[son].to_string())
+                .collect();
+            if sons.is_empty() {
+                break;
+            }
+            text += &format!("; children of {}: {}", self.items[idx], sons.join(", "));
+        }
+        text
+    }
+}
+
+/// Transforms that rely on the order comparing the values themselves: a
+/// monotone change to every element keeps every parent ahead of its sons.
+impl<P: ValueOrder> Heap<i32, P> {
+    /// Adds `k` to every element. The order is unchanged by this, so no
+    /// sifting happens.
+    ///
+    /// # Edge cases
+    /// * Returns Overflow error with the first index that would overflow,
+    ///   leaving the heap unchanged
+    pub fn add_all(&mut self, k: i32) -> Result<(), HeapError> {
+        self.transform_monotone(|x| x.checked_add(k))
+    }
+
+    /// Multiplies every element by `k`. A positive factor keeps the order,
+    /// so no sifting happens.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidScale error if k is not positive
+    /// * Returns Overflow error with the first index that would overflow,
+    ///   leaving the heap unchanged
+    pub fn scale_all(&mut self, k: i32) -> Result<(), HeapError> {
+        if k <= 0 {
+            return Err(HeapError::InvalidScale { k });
+        }
+        self.transform_monotone(|x| x.checked_mul(k))
+    }
+
+    /// Applies a non-decreasing `f` to every element, or nothing at all if
+    /// it fails for any of them.
+    fn transform_monotone(&mut self, f: impl Fn(i32) -> Option<i32>) -> Result<(), HeapError> {
+        if let Some(idx) = self.items.iter().position(|&x| f(x).is_none()) {
+            return Err(HeapError::Overflow { idx });
+        }
+        for item in &mut self.items {
+            *item = f(*item).unwrap_or(*item);
         }
+        self.check_invariants("transform_monotone");
+        Ok(())
+    }
+}
+
+/// Converts a binary heap into a d = 2 heap, taking over its allocation.
+/// BinaryHeap stores a max-heap in the same layout, so nothing is moved.
+impl<T: Ord> From<BinaryHeap<T>> for Heap<T> {
+    fn from(binary_heap: BinaryHeap<T>) -> Self {
+        Self::wrap(2, binary_heap.into_vec(), MaxOrder)
+    }
+}
+
+/// Converts a heap of any d and order into a BinaryHeap holding the same elements.
+impl<T: Ord, P: Priority<T>> From<Heap<T, P>> for BinaryHeap<T> {
+    fn from(heap: Heap<T, P>) -> Self {
+        BinaryHeap::from(heap.items)
+    }
+}
+
+/// Iterator over a heap's elements in priority order, created by `Heap::sorted_iter`.
+///
+/// Keeps an auxiliary binary heap of indices of the nodes whose parent was
+/// already yielded; since a node never outranks its parent, the next element is
+/// always in that frontier. Yielding k elements costs O(k * d * log(k * d)).
+pub struct SortedIter<'a, T = i32, P: Priority<T> = MaxOrder> {
+    heap: &'a Heap<T, P>,
+    frontier: Vec<usize>,
+}
+
+impl<T, P: Priority<T>> SortedIter<'_, T, P> {
+    /// Returns true if the node at frontier position `a` outranks the one at `b`.
+    fn outranks(&self, a: usize, b: usize) -> bool {
+        let items = &self.heap.items;
+        self.heap
+            .priority
+            .higher_than(&items[self.frontier[a]], &items[self.frontier[b]])
+    }
+
+    /// Adds a node index to the frontier, sifting it up by priority.
+    fn push(&mut self, idx: usize) {
+        self.frontier.push(idx);
+        let mut pos = self.frontier.len() - 1;
+        while pos > 0 && self.outranks(pos, (pos - 1) / 2) {
+            self.frontier.swap(pos, (pos - 1) / 2);
+            pos = (pos - 1) / 2;
+        }
+    }
+
+    /// Removes and returns the frontier's highest priority node index.
+    fn pop(&mut self) -> Option<usize> {
+        if self.frontier.is_empty() {
+            return None;
+        }
+
+        let top = self.frontier.swap_remove(0);
+        let mut pos = 0;
+        loop {
+            let mut best = pos;
+            for son in [2 * pos + 1, 2 * pos + 2] {
+                if son < self.frontier.len() && self.outranks(son, best) {
+                    best = son;
+                }
+            }
+            if best == pos {
+                return Some(top);
+            }
+            self.frontier.swap(pos, best);
+            pos = best;
+        }
+    }
+}
+
+impl<'a, T, P: Priority<T>> Iterator for SortedIter<'a, T, P> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let idx = self.pop()?;
+        for n_son in 0..self.heap.d {
+            if let Ok(son_idx) = self.heap.get_n_son(idx, n_son) {
+                self.push(son_idx);
+            }
+        }
+        Some(&self.heap.items[idx])
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::priority::MinOrder;
 
     #[test]
     fn test_heap_creation() {
-        let heap = Heap::new(2, &[3, 1, 4, 1, 5, 9]);
-        assert_eq!(heap.size, 6);
+        let heap = Heap::new(2, &[3, 1, 4, 1, 5, 9]).unwrap();
+        assert_eq!(heap.len(), 6);
     }
 
     #[test]
     fn test_insert() {
-        let mut heap = Heap::new(2, &[]);
+        let mut heap = Heap::new(2, &[]).unwrap();
         assert!(heap.insert(10).is_ok());
         assert!(heap.insert(20).is_ok());
-        assert_eq!(heap.size, 2);
-        assert_eq!(heap.array[0], 20); // Max-heap property
+        assert_eq!(heap.len(), 2);
+        assert_eq!(heap.items[0], 20); // Max-heap property
     }
 
     #[test]
     fn test_heapify_up() {
-        let mut heap = Heap::new(2, &[]);
+        let mut heap = Heap::new(2, &[]).unwrap();
         assert!(heap.insert(10).is_ok());
         assert!(heap.insert(20).is_ok());
         assert!(heap.insert(5).is_ok());
-        assert_eq!(heap.array[0], 20); // Max-heap property
+        assert_eq!(heap.items[0], 20); // Max-heap property
     }
 
     #[test]
     fn test_heapify_down() {
-        let mut heap = Heap::new(2, &[20, 10, 5]);
-        heap.array[0] = 1; // Break max-heap property
+        let mut heap = Heap::new(2, &[20, 10, 5]).unwrap();
+        heap.items[0] = 1; // Break max-heap property
         let res = heap.heapify_down(0);
         assert!(res.is_ok());
-        assert_eq!(heap.array[0], 10); // Max-heap property restored
+        assert_eq!(heap.items[0], 10); // Max-heap property restored
     }
     #[test]
     fn test_extract_max() {
-        let mut heap = Heap::new(2, &[3, 1, 4, 1, 5, 9]);
+        let mut heap = Heap::new(2, &[3, 1, 4, 1, 5, 9]).unwrap();
 
         // Extract max and verify it's correct
         let max_res = heap.extract_max();
@@ -248,15 +1199,15 @@ mod tests {
         assert_eq!(max_res.unwrap(), 9);
 
         // Verify the size decreased
-        assert_eq!(heap.size, 5);
+        assert_eq!(heap.len(), 5);
 
         // Verify heap property is maintained
-        for i in 0..heap.size {
+        for i in 0..heap.len() {
             if let Ok(left) = heap.get_n_son(i, 0) {
-                assert!(heap.array[i] >= heap.array[left]);
+                assert!(heap.items[i] >= heap.items[left]);
             }
             if let Ok(right) = heap.get_n_son(i, 1) {
-                assert!(heap.array[i] >= heap.array[right]);
+                assert!(heap.items[i] >= heap.items[right]);
             }
         }
 
@@ -268,7 +1219,7 @@ mod tests {
 
     #[test]
     fn test_get_parent() {
-        let heap = Heap::new(2, &[3, 1, 4, 1, 5, 9]);
+        let heap = Heap::new(2, &[3, 1, 4, 1, 5, 9]).unwrap();
         assert!(heap.get_parent(1).is_ok());
         assert_eq!(heap.get_parent(1).unwrap_or(999), 0);
         assert!(heap.get_parent(2).is_ok());
@@ -278,12 +1229,15 @@ mod tests {
 
     #[test]
     fn test_get_n_son() {
-        let heap = Heap::new(2, &[3, 1, 4, 1, 5, 9, 10, 12]);
+        let heap = Heap::new(2, &[3, 1, 4, 1, 5, 9, 10, 12]).unwrap();
         assert!(heap.get_n_son(0, 0).is_ok());
         assert_eq!(heap.get_n_son(0, 0).unwrap_or(999), 1);
         assert!(heap.get_n_son(0, 1).is_ok());
         assert_eq!(heap.get_n_son(0, 1).unwrap_or(999), 2);
-        assert!(heap.get_n_son(0, 2).is_err());
+        assert_eq!(
+            heap.get_n_son(0, 2),
+            Err(HeapError::InvalidSonIndex { idx: 0, n: 2, d: 2 })
+        );
 
         // Test for a node at a deeper level
         assert!(heap.get_n_son(1, 0).is_ok());
@@ -295,4 +1249,508 @@ mod tests {
         assert!(heap.get_n_son(2, 1).is_ok());
         assert_eq!(heap.get_n_son(2, 1).unwrap_or(999), 6);
     }
+
+    #[test]
+    fn test_is_heap() {
+        let mut heap = Heap::new(3, &[3, 1, 4, 1, 5, 9, 2, 6]).unwrap();
+        assert!(heap.is_heap());
+        heap.items[0] = -5; // Break max-heap property
+        assert!(!heap.is_heap());
+    }
+
+    #[test]
+    fn test_error_context() {
+        let mut heap = Heap::bounded(2, HEAP_MAX_SIZE, &[0; HEAP_MAX_SIZE + 1], MaxOrder).unwrap();
+        assert_eq!(heap.len(), HEAP_MAX_SIZE);
+        let err = heap.insert(1).unwrap_err();
+        assert_eq!(
+            err,
+            HeapError::HeapFull {
+                capacity: HEAP_MAX_SIZE
+            }
+        );
+        assert_eq!(err.to_string(), "heap is full (capacity 1000)");
+
+        let heap = Heap::new(2, &[3, 1]).unwrap();
+        assert_eq!(
+            heap.get_n_son(0, 1),
+            Err(HeapError::SonReachedEnd {
+                idx: 0,
+                n: 1,
+                size: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_d() {
+        assert_eq!(
+            Heap::new(0, &[1, 2, 3]).err(),
+            Some(HeapError::InvalidD { d: 0 })
+        );
+
+        let mut heap = Heap::new(2, &[1, 2, 3]).unwrap();
+        assert_eq!(heap.change_d(0), Err(HeapError::InvalidD { d: 0 }));
+        assert_eq!(heap.d, 2);
+        assert!(heap.change_d(3).is_ok());
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_from_sorted_desc() {
+        let heap = Heap::from_sorted_desc(3, &[9, 7, 7, 4, 1]).unwrap();
+        assert_eq!(heap.len(), 5);
+        assert!(heap.is_heap());
+
+        assert_eq!(
+            Heap::from_sorted_desc(3, &[9, 7, 8, 4]).err(),
+            Some(HeapError::NotSortedDesc { idx: 2 })
+        );
+    }
+
+    #[test]
+    fn test_from_heapified() {
+        let heap = Heap::from_heapified(2, &[9, 5, 8, 1, 2, 7]).unwrap();
+        assert_eq!(heap.as_slice(), &[9, 5, 8, 1, 2, 7]);
+
+        assert_eq!(
+            Heap::from_heapified(2, &[9, 5, 8, 6]).err(),
+            Some(HeapError::NotAHeap { idx: 3 })
+        );
+        assert_eq!(
+            Heap::from_heapified(0, &[1]).err(),
+            Some(HeapError::InvalidD { d: 0 })
+        );
+    }
+
+    #[test]
+    fn test_fast_paths_match_generic() {
+        let mut rng = crate::rng::Rng::new(688);
+        for d in [2, 4] {
+            for size in 0..40 {
+                let values: Vec<i32> = (0..size).map(|_| rng.range_i32(-3, 3)).collect();
+                let mut fast = Heap::from_raw(d, values.clone(), MaxOrder).unwrap();
+                let mut generic = Heap::from_raw(d, values.clone(), MaxOrder).unwrap();
+                for idx in (0..size).rev() {
+                    fast.heapify_down(idx).unwrap();
+                    generic.heapify_down_generic(idx).unwrap();
+                    assert_eq!(fast.as_slice(), generic.as_slice());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sorted_iter() {
+        let values = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3];
+        let heap = Heap::new(3, &values).unwrap();
+        let before = heap.items.clone();
+
+        let sorted: Vec<i32> = heap.sorted_iter().copied().collect();
+        let mut expected = values.to_vec();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(sorted, expected);
+
+        // The heap itself is not disturbed
+        assert_eq!(heap.items, before);
+        assert_eq!(heap.len(), values.len());
+        assert_eq!(Heap::new(2, &[]).unwrap().sorted_iter().next(), None);
+    }
+
+    #[test]
+    fn test_peek_and_replace_max() {
+        let mut heap = Heap::new(2, &[4, 8, 1]).unwrap();
+        assert_eq!(heap.peek_max(), Ok(&8));
+        assert_eq!(heap.replace_max(3), Ok(8));
+        assert_eq!(heap.peek_max(), Ok(&4));
+        assert_eq!(heap.len(), 3);
+        assert!(heap.is_heap());
+
+        let mut empty = Heap::new(2, &[]).unwrap();
+        assert_eq!(empty.peek_max(), Err(HeapError::EmptyHeap));
+        assert_eq!(empty.replace_max(1), Err(HeapError::EmptyHeap));
+    }
+
+    #[test]
+    fn test_binary_heap_conversions() {
+        let binary_heap = BinaryHeap::from(vec![3, 1, 4, 1, 5, 9, 2, 6]);
+        let heap = Heap::from(binary_heap);
+        assert_eq!(heap.d, 2);
+        assert_eq!(heap.len(), 8);
+        assert!(heap.is_heap());
+
+        let heap = Heap::new(3, &[7, 2, 8, 8]).unwrap();
+        let binary_heap = BinaryHeap::from(heap);
+        assert_eq!(binary_heap.into_sorted_vec(), vec![2, 7, 8, 8]);
+
+        let big = BinaryHeap::from((0..HEAP_MAX_SIZE as i32 + 1).collect::<Vec<_>>());
+        let heap = Heap::from(big);
+        assert_eq!(heap.len(), HEAP_MAX_SIZE + 1);
+        assert_eq!(heap.capacity(), None);
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_generic_items() {
+        let words = vec!["pear", "apple", "fig", "kiwi", "date"];
+        let max = Heap::from_vec(3, words.clone(), MaxOrder).unwrap();
+        assert!(max.is_heap());
+        assert_eq!(
+            max.sorted_iter().copied().collect::<Vec<&str>>(),
+            vec!["pear", "kiwi", "fig", "date", "apple"]
+        );
+
+        let mut min = Heap::with_priority(2, &words, MinOrder).unwrap();
+        assert_eq!(min.peek_max(), Ok(&"apple"));
+        min.change_d(4).unwrap();
+        assert_eq!(min.extract_max(), Ok("apple"));
+        assert_eq!(min.len(), 4);
+
+        let mut bounded = Heap::bounded(2, 2, &[], MaxOrder).unwrap();
+        bounded.insert(String::from("a")).unwrap();
+        bounded.insert(String::from("b")).unwrap();
+        assert_eq!(
+            bounded.insert(String::from("c")),
+            Err(HeapError::HeapFull { capacity: 2 })
+        );
+        assert_eq!(bounded.extract_max().as_deref(), Ok("b"));
+    }
+
+    #[test]
+    fn test_grows_past_heap_max_size() {
+        let mut heap = Heap::new(2, &[]).unwrap();
+        for i in 0..5000 {
+            heap.insert(i * 7919 % 5003).unwrap();
+        }
+        assert_eq!(heap.capacity(), None);
+        assert_eq!(heap.len(), 5000);
+        let sorted: Vec<i32> = heap.sorted_iter().copied().collect();
+        assert!(sorted.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_sift_steps() {
+        let mut heap = Heap::new(2, &[]).unwrap();
+        assert_eq!(heap.sift_steps(), 0);
+        assert!(heap.insert(1).is_ok());
+        assert!(heap.insert(2).is_ok());
+        assert_eq!(heap.sift_steps(), 1); // 2 swaps with its parent 1
+        assert!(heap.insert(0).is_ok());
+        assert_eq!(heap.sift_steps(), 1);
+        assert!(heap.extract_max().is_ok());
+        assert_eq!(heap.sift_steps(), 2); // 0 moves to the root and swaps with 1
+    }
+
+    #[test]
+    fn test_min_order() {
+        let values = [3, 1, 4, 1, 5, 9, 2, 6];
+        let mut heap = Heap::with_priority(3, &values, MinOrder).unwrap();
+        assert!(heap.is_heap());
+        assert!(heap.insert(-7).is_ok());
+        assert!(heap.insert(10).is_ok());
+
+        let sorted: Vec<i32> = heap.sorted_iter().copied().collect();
+        assert_eq!(sorted, vec![-7, 1, 1, 2, 3, 4, 5, 6, 9, 10]);
+        for expected in sorted {
+            assert_eq!(heap.extract_max(), Ok(expected));
+        }
+        assert!(heap.extract_max().is_err());
+    }
+
+    #[test]
+    fn test_heapified_input_skips_build() {
+        let heap = Heap::new(3, &[9, 5, 8, 7, 1, 2, 4]).unwrap();
+        assert_eq!(heap.sift_steps(), 0);
+        assert_eq!(heap.as_slice(), &[9, 5, 8, 7, 1, 2, 4]);
+
+        let heap = Heap::new(3, &[1, 5, 8, 7]).unwrap();
+        assert!(heap.sift_steps() > 0);
+        assert!(heap.is_heap());
+    }
+
+    #[test]
+    fn test_from_slice_unchecked() {
+        let heap = Heap::from_slice_unchecked(2, &[1, 5, 8], MaxOrder).unwrap();
+        assert_eq!(heap.as_slice(), &[1, 5, 8]);
+        assert!(!heap.is_heap());
+    }
+
+    #[test]
+    fn test_map_in_place() {
+        let mut heap = Heap::new(3, &[5, -8, 2, 7, -1]).unwrap();
+        assert!(heap.map_in_place(|x| *x *= *x).is_ok());
+        assert!(heap.is_heap());
+        assert_eq!(
+            heap.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![64, 49, 25, 4, 1]
+        );
+    }
+
+    #[test]
+    fn test_monotone_transforms() {
+        let mut heap = Heap::with_priority(2, &[3, -4, 8, 0, 5], MinOrder).unwrap();
+        let layout = heap.as_slice().to_vec();
+        let steps = heap.sift_steps();
+        assert!(heap.add_all(10).is_ok());
+        assert!(heap.scale_all(3).is_ok());
+        assert_eq!(heap.sift_steps(), steps);
+        let expected: Vec<i32> = layout.iter().map(|x| (x + 10) * 3).collect();
+        assert_eq!(heap.as_slice(), &expected[..]);
+        assert_eq!(
+            heap.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![18, 30, 39, 45, 54]
+        );
+
+        assert_eq!(heap.scale_all(0), Err(HeapError::InvalidScale { k: 0 }));
+        let first_too_big = expected.iter().position(|&x| x > 40).unwrap();
+        assert_eq!(
+            heap.add_all(i32::MAX - 40),
+            Err(HeapError::Overflow { idx: first_too_big })
+        );
+        assert_eq!(heap.as_slice(), &expected[..]);
+    }
+
+    #[test]
+    fn test_describe_plain() {
+        let heap = Heap::new(2, &[20, 10, 5, 3]).unwrap();
+        assert_eq!(
+            heap.describe_plain(),
+            "heap with d=2 and 4 elements: root 20; children of 20: 10, 5; children of 10: 3"
+        );
+        let empty = Heap::new(3, &[]).unwrap();
+        assert_eq!(empty.describe_plain(), "heap with d=3 and 0 elements");
+    }
+
+    #[test]
+    fn test_leaves_and_internal_nodes() {
+        let heap = Heap::new(3, &[9, 5, 8, 7, 1, 2, 4, 3]).unwrap();
+        let internal: Vec<usize> = heap.internal_nodes().map(|(i, _)| i).collect();
+        let leaves: Vec<usize> = heap.leaves().map(|(i, _)| i).collect();
+        assert_eq!(internal, vec![0, 1, 2]);
+        assert_eq!(leaves, vec![3, 4, 5, 6, 7]);
+
+        let min = heap.as_slice().iter().min();
+        assert_eq!(heap.leaves().map(|(_, x)| x).min(), min);
+
+        for d in 1..=5 {
+            let heap = Heap::new(d, &[1, 2, 3, 4, 5, 6, 7]).unwrap();
+            assert!(heap.is_heap());
+            for (i, _) in heap.internal_nodes() {
+                assert!(heap.get_n_son(i, 0).is_ok());
+            }
+            for (i, _) in heap.leaves() {
+                assert!(heap.get_n_son(i, 0).is_err());
+            }
+        }
+        assert_eq!(Heap::new(2, &[]).unwrap().leaves().count(), 0);
+        assert_eq!(Heap::new(2, &[1]).unwrap().leaves().count(), 1);
+    }
+
+    #[test]
+    fn test_path_to_root() {
+        let heap = Heap::new(3, &[9, 5, 8, 7, 1, 2, 4, 3]).unwrap();
+        assert_eq!(heap.path_to_root(7), Ok(vec![(7, &3), (2, &8), (0, &9)]));
+        assert_eq!(heap.path_to_root(0), Ok(vec![(0, &9)]));
+        assert_eq!(
+            heap.path_to_root(8),
+            Err(HeapError::IndexOutOfRange { idx: 8, size: 8 })
+        );
+    }
+
+    #[test]
+    fn test_children() {
+        let heap = Heap::new(3, &[9, 5, 8, 7, 1, 2, 4, 3]).unwrap();
+        assert_eq!(heap.children(2), Ok(vec![Some((7, &3)), None, None]));
+        assert_eq!(heap.children(3), Ok(vec![None, None, None]));
+        assert!(heap.children(8).is_err());
+    }
+
+    #[cfg(feature = "debug-invariants")]
+    #[test]
+    #[should_panic(expected = "insert broke the heap property")]
+    fn test_debug_invariants_catch_corruption() {
+        let mut heap = Heap::from_slice_unchecked(2, &[1, 5, 8], MaxOrder).unwrap();
+        let _ = heap.insert(0);
+    }
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        assert_eq!(HeapError::HeapFull { capacity: 5 }.code() as i32, 1);
+        assert_eq!(HeapError::EmptyHeap.code() as i32, 2);
+        assert_eq!(HeapError::InvalidD { d: 0 }.code() as i32, 7);
+        assert_eq!(
+            HeapError::IndexOutOfRange { idx: 3, size: 1 }.code() as i32,
+            12
+        );
+        let handle = Heap::new(2, &[1]).unwrap().handle_at(0).unwrap();
+        assert_eq!(HeapError::UnknownHandle { handle }.code() as i32, 13);
+        assert_eq!(HeapError::InvalidKeyChange { handle }.code() as i32, 14);
+        assert_eq!(HeapError::InvalidFile { line: 1 }.code() as i32, 16);
+        assert_eq!(HeapError::Inconsistent { reason: "" }.code() as i32, 17);
+        assert_eq!(HeapError::WorkerStopped.code() as i32, 18);
+        assert_eq!(HeapError::StaleHandle { handle }.code() as i32, 19);
+    }
+
+    #[test]
+    fn test_handles_follow_their_elements() {
+        let mut heap = Heap::new(3, &[]).unwrap();
+        let handles: Vec<Handle> = [5, 9, 1, 7, 3, 8, 2]
+            .iter()
+            .map(|&x| heap.insert(x).unwrap())
+            .collect();
+
+        assert_eq!(heap.increase_key(handles[2], 10), Ok(1));
+        assert_eq!(heap.peek_max(), Ok(&10));
+        assert_eq!(heap.handle_at(0), Ok(handles[2]));
+        assert_eq!(heap.decrease_key(handles[1], 0), Ok(9));
+        assert_eq!(heap.delete(handles[3]), Ok(7));
+        heap.change_d(2).unwrap();
+        assert!(heap.is_heap());
+
+        assert_eq!(heap.get(handles[5]), Ok(&8));
+        let order: Vec<(Handle, i32)> = std::iter::from_fn(|| {
+            let handle = heap.handle_at(0).ok()?;
+            heap.extract_max().ok().map(|x| (handle, x))
+        })
+        .collect();
+        assert_eq!(
+            order,
+            vec![
+                (handles[2], 10),
+                (handles[5], 8),
+                (handles[0], 5),
+                (handles[4], 3),
+                (handles[6], 2),
+                (handles[1], 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dijkstra_style_decrease_key() {
+        let mut heap = Heap::with_priority(4, &[], MinOrder).unwrap();
+        let far = heap.insert(100).unwrap();
+        let near = heap.insert(50).unwrap();
+        assert_eq!(heap.increase_key(far, 10), Ok(100));
+        assert_eq!(heap.position(far), Ok(0));
+        assert_eq!(heap.extract_max(), Ok(10));
+        assert_eq!(heap.position(near), Ok(0));
+    }
+
+    #[test]
+    fn test_handle_edge_cases() {
+        let mut heap = Heap::new(2, &[4]).unwrap();
+        let only = heap.handle_at(0).unwrap();
+        assert_eq!(
+            heap.increase_key(only, 3),
+            Err(HeapError::InvalidKeyChange { handle: only })
+        );
+        assert_eq!(
+            heap.decrease_key(only, 5),
+            Err(HeapError::InvalidKeyChange { handle: only })
+        );
+        assert_eq!(heap.delete(only), Ok(4));
+        assert_eq!(
+            heap.delete(only),
+            Err(HeapError::StaleHandle { handle: only })
+        );
+        assert_eq!(
+            heap.handle_at(0),
+            Err(HeapError::IndexOutOfRange { idx: 0, size: 0 })
+        );
+
+        let reused = heap.insert(6).unwrap();
+        assert_ne!(reused, only);
+        assert_eq!(heap.get(only), Err(HeapError::StaleHandle { handle: only }));
+        assert_eq!(heap.get(reused), Ok(&6));
+    }
+
+    #[test]
+    fn test_stale_and_unknown_handles_differ() {
+        let mut heap = Heap::new(2, &[]).unwrap();
+        let gone = heap.insert(1).unwrap();
+        heap.delete(gone).unwrap();
+        let never: Handle = "3.0".parse().unwrap();
+        let stale = HeapError::StaleHandle { handle: gone };
+        let unknown = HeapError::UnknownHandle { handle: never };
+
+        assert_eq!(heap.increase_key(gone, 5), Err(stale));
+        assert_eq!(heap.decrease_key(never, 0), Err(unknown));
+        assert_eq!(
+            HeapError::StaleHandle { handle: gone }.to_string(),
+            "handle 0.0 is stale: its element left the heap"
+        );
+        assert_eq!(
+            HeapError::UnknownHandle { handle: never }.to_string(),
+            "handle 3.0 was never issued by this heap"
+        );
+
+        // A generation the slot has not reached yet was never issued either
+        let future: Handle = "0.2".parse().unwrap();
+        assert_eq!(
+            heap.get(future),
+            Err(HeapError::UnknownHandle { handle: future })
+        );
+    }
+
+    #[test]
+    fn test_handle_slots_are_reused() {
+        let mut heap = Heap::new(2, &[3, 1, 2]).unwrap();
+        for x in 0..100 {
+            heap.insert(x).unwrap();
+            heap.extract_max().unwrap();
+            let old = heap.replace_max(x).unwrap();
+            heap.insert(old).unwrap();
+            let handle = heap.handle_at(heap.len() - 1).unwrap();
+            heap.delete(handle).unwrap();
+        }
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.table.slots(), 4);
+        assert!(heap.handles().all(|(h, x)| heap.get(h) == Ok(x)));
+    }
+
+    #[test]
+    fn test_heap_bytes_counts_handles() {
+        let mut heap = Heap::bounded(2, 100, &[], MaxOrder).unwrap();
+        let buffers = 100 * (mem::size_of::<i32>() + mem::size_of::<Handle>());
+        assert_eq!(heap.heap_bytes(), buffers);
+        for x in 0..10 {
+            heap.insert(x).unwrap();
+        }
+        assert_eq!(heap.heap_bytes(), buffers + heap.table.heap_bytes());
+        assert!(heap.table.heap_bytes() >= 10 * mem::size_of::<Handle>());
+    }
+
+    #[test]
+    fn test_remove_at_and_lowest() {
+        let mut heap = Heap::new(3, &[9, 5, 8, 7, 1, 2, 4, 3]).unwrap();
+        assert_eq!(heap.lowest(), Some((4, &1)));
+        assert_eq!(heap.remove_at(1), Ok(5));
+        assert!(heap.is_heap());
+        assert_eq!(heap.remove_at(heap.len() - 1).map(|_| heap.len()), Ok(6));
+        assert!(heap.is_heap());
+        assert_eq!(
+            heap.remove_at(6),
+            Err(HeapError::IndexOutOfRange { idx: 6, size: 6 })
+        );
+
+        let min_heap = Heap::with_priority(2, &[4, 8, 6, 9], MinOrder).unwrap();
+        assert_eq!(min_heap.lowest().map(|(_, &x)| x), Some(9));
+        assert_eq!(Heap::new(2, &[]).unwrap().lowest(), None);
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut heap = Heap::new(2, &[9, 7, 8, 3, 5, 6, 1]).unwrap();
+        assert_eq!(heap.repair(), Ok(0));
+
+        heap.items[5] = 10;
+        heap.items[3] = 20;
+        assert_eq!(heap.repair(), Ok(2));
+        assert!(heap.is_heap());
+        assert_eq!(
+            heap.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![20, 10, 9, 8, 7, 5, 1]
+        );
+    }
 }
@@ -0,0 +1,247 @@
+use crate::heap::{Heap, HeapError};
+use crate::priority::Priority;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+/// Most staged items the heapifier applies under a single lock.
+const BATCH_SIZE: usize = 256;
+
+/// Counters shared between producers, the heapifier and the Ingestor.
+#[derive(Default)]
+struct Progress {
+    pushed: AtomicUsize,
+    rejected: AtomicUsize,
+    applied: Mutex<Applied>,
+    /// Signalled by the heapifier after every batch and when it stops.
+    changed: Condvar,
+}
+
+/// What the heapifier has done so far, guarded by Progress::applied.
+#[derive(Default)]
+struct Applied {
+    count: usize,
+    /// Set as the heapifier exits, since the thread may not report
+    /// is_finished yet when flush wakes up.
+    stopped: bool,
+}
+
+impl Progress {
+    fn applied(&self) -> MutexGuard<'_, Applied> {
+        self.applied.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Marks the heapifier stopped and wakes flush when its thread exits, even
+/// by panicking.
+struct StopGuard<'a>(&'a Progress);
+
+impl Drop for StopGuard<'_> {
+    fn drop(&mut self) {
+        self.0.applied().stopped = true;
+        self.0.changed.notify_all();
+    }
+}
+
+/// Feeds a heap from many threads without making producers pay for sifting.
+///
+/// Producers append raw items to a lock-free channel; a background heapifier
+/// thread drains it and inserts up to BATCH_SIZE items per lock of the heap.
+/// Items that arrive while the heap is full are counted as rejected.
+pub struct Ingestor<P: Priority + Send + 'static> {
+    heap: Arc<Mutex<Heap<i32, P>>>,
+    sender: Sender<i32>,
+    progress: Arc<Progress>,
+    worker: JoinHandle<()>,
+}
+
+/// Cloneable handle for pushing items into an Ingestor from any thread.
+#[derive(Clone)]
+pub struct Producer {
+    sender: Sender<i32>,
+    progress: Arc<Progress>,
+}
+
+impl Producer {
+    /// Stages an item for the heapifier without waiting for it to be inserted.
+    ///
+    /// # Edge cases
+    /// * Returns false if the Ingestor was already finished
+    pub fn push(&self, item: i32) -> bool {
+        self.progress.pushed.fetch_add(1, Ordering::SeqCst);
+        if self.sender.send(item).is_ok() {
+            true
+        } else {
+            self.progress.pushed.fetch_sub(1, Ordering::SeqCst);
+            false
+        }
+    }
+}
+
+impl<P: Priority + Send + 'static> Ingestor<P> {
+    /// Starts the heapifier thread around an existing heap.
+    pub fn new(heap: Heap<i32, P>) -> Self {
+        let heap = Arc::new(Mutex::new(heap));
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = mpsc::channel();
+
+        let worker = {
+            let heap = Arc::clone(&heap);
+            let progress = Arc::clone(&progress);
+            thread::spawn(move || heapify_batches(&heap, &receiver, &progress))
+        };
+
+        Self {
+            heap,
+            sender,
+            progress,
+            worker,
+        }
+    }
+
+    /// Returns a new producer handle.
+    pub fn producer(&self) -> Producer {
+        Producer {
+            sender: self.sender.clone(),
+            progress: Arc::clone(&self.progress),
+        }
+    }
+
+    /// Blocks until every item pushed before this call has been applied,
+    /// sleeping on a condition variable the heapifier signals.
+    ///
+    /// # Edge cases
+    /// * Returns WorkerStopped error if the heapifier exited, e.g. because
+    ///   the heap's order panicked, so the items can never be applied
+    pub fn flush(&self) -> Result<(), HeapError> {
+        let target = self.progress.pushed.load(Ordering::SeqCst);
+        let mut applied = self.progress.applied();
+        while applied.count < target {
+            if applied.stopped || self.worker.is_finished() {
+                return Err(HeapError::WorkerStopped);
+            }
+            applied = self
+                .progress
+                .changed
+                .wait(applied)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        Ok(())
+    }
+
+    /// Returns how many items were dropped because the heap was full.
+    pub fn rejected(&self) -> usize {
+        self.progress.rejected.load(Ordering::SeqCst)
+    }
+
+    /// Runs `f` with the heap locked, e.g. to extract items. Items still in the
+    /// staging channel are not visible yet; call flush first to include them.
+    pub fn with_heap<R>(&self, f: impl FnOnce(&mut Heap<i32, P>) -> R) -> R {
+        let mut heap = self.heap.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut heap)
+    }
+
+    /// Stops the heapifier and returns the heap with all staged items applied.
+    ///
+    /// # Edge cases
+    /// * Waits until every Producer handle has been dropped, since the
+    ///   heapifier only stops once nobody can stage more items
+    pub fn finish(self) -> Heap<i32, P> {
+        drop(self.sender);
+        let _ = self.worker.join();
+        match Arc::try_unwrap(self.heap) {
+            Ok(heap) => heap.into_inner().unwrap_or_else(|e| e.into_inner()),
+            Err(_) => unreachable!("the heapifier thread holds the only other reference"),
+        }
+    }
+}
+
+/// Heapifier loop: waits for an item, grabs whatever else is already staged up
+/// to BATCH_SIZE, and inserts the batch under one lock.
+fn heapify_batches<P: Priority>(
+    heap: &Mutex<Heap<i32, P>>,
+    receiver: &Receiver<i32>,
+    progress: &Progress,
+) {
+    let _stop = StopGuard(progress);
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    while let Ok(first) = receiver.recv() {
+        batch.push(first);
+        batch.extend(receiver.try_iter().take(BATCH_SIZE - 1));
+
+        let mut heap = heap.lock().unwrap_or_else(|e| e.into_inner());
+        for &item in &batch {
+            if let Err(HeapError::HeapFull { .. }) = heap.insert(item) {
+                progress.rejected.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        drop(heap);
+
+        progress.applied().count += batch.len();
+        progress.changed.notify_all();
+        batch.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heap::HEAP_MAX_SIZE;
+    use crate::priority::{KeyOrder, MaxOrder};
+
+    #[test]
+    fn test_many_producers() {
+        let ingestor = Ingestor::new(Heap::new(4, &[]).unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let producer = ingestor.producer();
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        assert!(producer.push(t * 100 + i));
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        assert_eq!(ingestor.flush(), Ok(()));
+        assert_eq!(ingestor.with_heap(|h| h.len()), 400);
+        assert_eq!(ingestor.with_heap(|h| h.peek_max().copied()), Ok(399));
+
+        let heap = ingestor.finish();
+        assert!(heap.is_heap());
+        assert_eq!(heap.len(), 400);
+    }
+
+    #[test]
+    fn test_rejects_when_full() {
+        let heap = Heap::bounded(2, HEAP_MAX_SIZE, &[], MaxOrder).unwrap();
+        let ingestor = Ingestor::new(heap);
+        let producer = ingestor.producer();
+        for i in 0..(HEAP_MAX_SIZE + 10) {
+            producer.push(i as i32);
+        }
+        drop(producer);
+
+        assert_eq!(ingestor.flush(), Ok(()));
+        assert_eq!(ingestor.rejected(), 10);
+        assert_eq!(ingestor.finish().len(), HEAP_MAX_SIZE);
+    }
+
+    #[test]
+    fn test_flush_reports_a_stopped_worker() {
+        let order = KeyOrder::new(|&x: &i32| {
+            assert_ne!(x, 13, "unlucky item");
+            x as i64
+        });
+        let ingestor = Ingestor::new(Heap::with_priority(2, &[1], order).unwrap());
+        assert!(ingestor.producer().push(5));
+        assert_eq!(ingestor.flush(), Ok(()));
+
+        ingestor.producer().push(13);
+        assert_eq!(ingestor.flush(), Err(HeapError::WorkerStopped));
+    }
+}
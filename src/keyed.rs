@@ -0,0 +1,161 @@
+use crate::handles::Handle;
+use crate::heap::Heap;
+use crate::priority::{MaxOrder, Priority};
+
+/// Branching factor of the queue's heap; 4 keeps sifts shallow and takes
+/// the unrolled sift_down_fixed path.
+const D: u32 = 4;
+
+/// A queued value with its key and arrival number.
+#[derive(Clone)]
+struct Entry<K, V> {
+    key: K,
+    seq: u64,
+    value: V,
+}
+
+/// Ranks entries by key under `P`, then by arrival, so equal keys are FIFO.
+#[derive(Clone)]
+struct ByKey<P>(P);
+
+impl<K, V, P: Priority<K>> Priority<Entry<K, V>> for ByKey<P> {
+    fn higher_than(&self, a: &Entry<K, V>, b: &Entry<K, V>) -> bool {
+        self.0.higher_than(&a.key, &b.key) || (!self.0.higher_than(&b.key, &a.key) && a.seq < b.seq)
+    }
+}
+
+/// A priority queue of values ordered by a separate key, on the crate's
+/// Heap. With the default MaxOrder the largest key comes out first;
+/// `KeyedQueue<K, V, MinOrder>` pops the smallest. Values with equal keys
+/// come out in the order they were pushed.
+#[derive(Clone)]
+pub struct KeyedQueue<K, V, P: Priority<K> = MaxOrder> {
+    heap: Heap<Entry<K, V>, ByKey<P>>,
+    next_seq: u64,
+}
+
+impl<K: Ord, V> KeyedQueue<K, V> {
+    /// Creates an empty queue that pops the largest key first.
+    pub fn new() -> Self {
+        Self::with_priority(MaxOrder)
+    }
+}
+
+impl<K: Ord, V> Default for KeyedQueue<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, P: Priority<K>> KeyedQueue<K, V, P> {
+    /// Creates an empty queue whose keys are ranked by `priority`.
+    pub fn with_priority(priority: P) -> Self {
+        match Heap::from_vec(D, Vec::new(), ByKey(priority)) {
+            Ok(heap) => Self { heap, next_seq: 0 },
+            Err(_) => unreachable!("D is not 0"),
+        }
+    }
+
+    /// Queues `value` under `key`, returning a handle that remove takes.
+    pub fn push(&mut self, key: K, value: V) -> Handle {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        match self.heap.insert(Entry { key, seq, value }) {
+            Ok(handle) => handle,
+            Err(_) => unreachable!("only bounded heaps refuse an insert"),
+        }
+    }
+
+    /// Removes the value pushed under the handle, returning it with its key,
+    /// or None if it has already left the queue.
+    pub fn remove(&mut self, handle: Handle) -> Option<(K, V)> {
+        let entry = self.heap.delete(handle).ok()?;
+        Some((entry.key, entry.value))
+    }
+
+    /// Removes and returns the highest ranked key and its value, or None if
+    /// the queue is empty.
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let entry = self.heap.extract_max().ok()?;
+        Some((entry.key, entry.value))
+    }
+
+    /// Returns the key and value pop would return next.
+    pub fn peek(&self) -> Option<(&K, &V)> {
+        let entry = self.heap.peek_max().ok()?;
+        Some((&entry.key, &entry.value))
+    }
+
+    /// Returns the queued (key, value) pairs in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.heap
+            .as_slice()
+            .iter()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    /// Returns the queued (key, value) pairs in the order pop would return
+    /// them, leaving the queue untouched.
+    pub fn sorted_iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.heap
+            .sorted_iter()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    /// Returns the number of queued values.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns true if nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MinOrder;
+
+    #[test]
+    fn test_pops_by_key_then_arrival() {
+        let mut queue = KeyedQueue::new();
+        for (key, value) in [(2, 'a'), (5, 'b'), (2, 'c'), (5, 'd'), (1, 'e')] {
+            queue.push(key, value);
+        }
+        assert_eq!(queue.peek(), Some((&5, &'b')));
+        let order: Vec<char> = std::iter::from_fn(|| queue.pop().map(|(_, v)| v)).collect();
+        assert_eq!(order, vec!['b', 'd', 'a', 'c', 'e']);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_min_order_and_non_copy_values() {
+        let mut queue = KeyedQueue::with_priority(MinOrder);
+        queue.push(30u64, "late".to_string());
+        queue.push(10, "early".to_string());
+        queue.push(10, "early too".to_string());
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.iter().filter(|&(&k, _)| k == 10).count(), 2);
+        assert_eq!(queue.pop(), Some((10, "early".to_string())));
+        assert_eq!(queue.pop(), Some((10, "early too".to_string())));
+        assert_eq!(queue.pop(), Some((30, "late".to_string())));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_remove_and_sorted_iter() {
+        let mut queue = KeyedQueue::new();
+        let handles: Vec<Handle> = [4, 9, 4, 1]
+            .iter()
+            .map(|&k| queue.push(k, k * 10))
+            .collect();
+        assert_eq!(queue.remove(handles[1]), Some((9, 90)));
+        assert_eq!(queue.remove(handles[1]), None);
+        let sorted: Vec<(i32, i32)> = queue.sorted_iter().map(|(&k, &v)| (k, v)).collect();
+        assert_eq!(sorted, vec![(4, 40), (4, 40), (1, 10)]);
+        assert_eq!(queue.remove(handles[3]), Some((1, 10)));
+        assert_eq!(queue.len(), 2);
+    }
+}
@@ -0,0 +1,39 @@
+//! D-ary heaps and the priority queues built on them. The `d-heap`
+//! binary is an interactive menu and a set of subcommands over this library.
+
+pub mod aging;
+pub mod algorithms;
+pub mod batch;
+pub mod bounded;
+pub mod calendar;
+pub mod const_heap;
+pub mod cow;
+pub mod demo;
+pub mod expr;
+pub mod extsort;
+pub mod fair;
+pub mod handles;
+pub mod heap;
+pub mod ingest;
+pub mod keyed;
+pub mod merge;
+pub mod model;
+pub mod multiqueue;
+pub mod partial_sort;
+pub mod persist;
+pub mod priority;
+pub mod rate_limit;
+pub mod rng;
+pub mod select;
+pub mod sharded;
+pub mod shared;
+pub mod skew_binomial;
+pub mod snapshot;
+pub mod soak;
+#[cfg(feature = "stream")]
+pub mod stream;
+pub mod stress;
+pub mod timestamp;
+pub mod timing_wheel;
+pub mod top_k;
+pub mod trace;
@@ -1,7 +1,292 @@
-mod heap;
-
-use heap::Heap;
+use d_heap::bounded::{BoundedHeap, Inserted, OverflowPolicy};
+use d_heap::expr::Expr;
+use d_heap::handles::Handle;
+use d_heap::heap::HEAP_MAX_SIZE;
+use d_heap::priority::KeyOrder;
+use d_heap::trace::{Trace, TraceRecord};
+use d_heap::{batch, demo, extsort, select, soak, stress};
+use std::env;
 use std::io::{self, Write};
+use std::mem;
+use std::process;
+use std::time::{Duration, Instant};
+
+/// Key function behind the menu's heap, so every --order maps onto KeyOrder.
+type OrderKey = Box<dyn Fn(&i32) -> i64>;
+type MenuHeap = BoundedHeap<KeyOrder<OrderKey>>;
+
+/// Ordering chosen with --order for every heap built from the menu.
+enum OrderOption {
+    Max,
+    Min,
+    Abs,
+    Custom(String, Expr),
+}
+
+impl OrderOption {
+    /// Creates a fresh comparator for a newly built heap.
+    fn priority(&self) -> KeyOrder<OrderKey> {
+        match self {
+            OrderOption::Max => KeyOrder::new(Box::new(|&x| x as i64)),
+            OrderOption::Min => KeyOrder::new(Box::new(|&x| -(x as i64))),
+            OrderOption::Abs => KeyOrder::new(Box::new(|&x| (x as i64).abs())),
+            OrderOption::Custom(_, expr) => {
+                let expr = expr.clone();
+                KeyOrder::new(Box::new(move |&x| expr.eval(x as i64)))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            OrderOption::Max => "max".to_string(),
+            OrderOption::Min => "min".to_string(),
+            OrderOption::Abs => "largest absolute value first".to_string(),
+            OrderOption::Custom(text, _) => format!("largest '{}' first", text),
+        }
+    }
+}
+
+/// Options for the interactive menu, parsed from the command line.
+struct MenuOptions {
+    stats: bool,
+    plain: bool,
+    array: bool,
+    order: OrderOption,
+    on_full: OverflowPolicy,
+    trace: Option<Trace>,
+}
+
+/// Parses `--stats`, `--plain`, `--array`, `--on-full reject|evict-min|evict-oldest`,
+/// `--trace <file>`, `--order max|min|abs|custom` and `--key <expr>`, where the
+/// key expression is required by and only allowed with `--order custom`.
+fn parse_menu_options(args: &[String]) -> Result<MenuOptions, String> {
+    let mut stats = false;
+    let mut plain = false;
+    let mut array = false;
+    let mut order = None;
+    let mut key = None;
+    let mut on_full = OverflowPolicy::Reject;
+    let mut trace = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--stats" => stats = true,
+            "--plain" => plain = true,
+            "--array" => array = true,
+            "--on-full" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))?;
+                on_full = OverflowPolicy::parse(value)?;
+            }
+            "--trace" => {
+                let path = iter
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))?;
+                let opened = Trace::open(path)
+                    .map_err(|e| format!("Failed to open trace file {}: {}", path, e))?;
+                trace = Some(opened);
+            }
+            "--order" | "--key" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {}", arg))?;
+                if arg == "--order" {
+                    order = Some(value.as_str());
+                } else {
+                    key = Some(value.as_str());
+                }
+            }
+            _ => return Err(format!("Unknown option: {}", arg)),
+        }
+    }
+
+    let order = match (order.unwrap_or("max"), key) {
+        ("max", None) => OrderOption::Max,
+        ("min", None) => OrderOption::Min,
+        ("abs", None) => OrderOption::Abs,
+        ("custom", Some(text)) => OrderOption::Custom(text.to_string(), Expr::parse(text)?),
+        ("custom", None) => return Err("--order custom needs --key <expr>".to_string()),
+        ("max" | "min" | "abs", Some(_)) => {
+            return Err("--key is only used with --order custom".to_string())
+        }
+        (other, _) => return Err(format!("Unknown order: {}", other)),
+    };
+
+    Ok(MenuOptions {
+        stats,
+        plain,
+        array,
+        order,
+        on_full,
+        trace,
+    })
+}
+
+/// Cost of a single heap operation, printed after it with --stats.
+struct OpStats {
+    elapsed: Duration,
+    sift_steps: u64,
+}
+
+impl OpStats {
+    fn print(&self, heap: &MenuHeap) {
+        println!(
+            "[stats] {} us, {} sift steps, {}/{} slots used ({} bytes)",
+            self.elapsed.as_micros(),
+            self.sift_steps,
+            heap.len(),
+            heap.capacity(),
+            mem::size_of::<MenuHeap>() + heap.heap_bytes()
+        );
+    }
+}
+
+/// Runs a heap operation, recording how long it took and how many swaps it made.
+fn measure<T>(heap: &mut MenuHeap, op: impl FnOnce(&mut MenuHeap) -> T) -> (T, OpStats) {
+    let steps_before = heap.sift_steps();
+    let started = Instant::now();
+    let result = op(heap);
+    let stats = OpStats {
+        elapsed: started.elapsed(),
+        sift_steps: heap.sift_steps() - steps_before,
+    };
+    (result, stats)
+}
+
+/// Prints the heap by levels, or as a single linear description with --plain
+/// so a screen reader can follow it. With --array each level is followed by
+/// the backing array slots it occupies.
+fn show_heap(heap: &MenuHeap, options: &MenuOptions) {
+    if options.plain {
+        println!("{}", heap.describe_plain());
+    } else if options.array {
+        print_tree_and_array(heap);
+    } else {
+        heap.print();
+    }
+}
+
+/// Prints each tree level next to the same values as `index:value` slots of
+/// the backing array, showing where every level lives in the array.
+fn print_tree_and_array(heap: &MenuHeap) {
+    let values = heap.as_slice();
+    println!("Heap (d={}), tree | array", heap.d());
+    if values.is_empty() {
+        println!("Empty heap :(");
+        return;
+    }
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut count = 1;
+    while start < values.len() {
+        let end = values.len().min(start + count);
+        let tree: Vec<String> = values[start..end].iter().map(|x| x.to_string()).collect();
+        let slots: Vec<String> = (start..end)
+            .map(|i| format!("{}:{}", i, values[i]))
+            .collect();
+        rows.push((
+            format!("Level {}: {}", rows.len(), tree.join(" ")),
+            slots.join(" "),
+        ));
+        start = end;
+        count *= heap.d() as usize;
+    }
+
+    let width = rows.iter().map(|(tree, _)| tree.len()).max().unwrap_or(0);
+    for (tree, slots) in rows {
+        println!("{:<width$} | {}", tree, slots, width = width);
+    }
+}
+
+/// Returns the slots whose value changed since `before`, ordered along the
+/// sift: from the leaf up for an insert, from the root down otherwise.
+fn sift_path(before: &[i32], heap: &MenuHeap, upward: bool) -> Vec<usize> {
+    let after = heap.as_slice();
+    let mut changed: Vec<usize> = (0..after.len())
+        .filter(|&i| before.get(i) != Some(&after[i]))
+        .collect();
+    if upward {
+        changed.reverse();
+    }
+    changed
+}
+
+/// Prints the heap after an insert or extract with every slot on the sift
+/// path shown in brackets, followed by the path itself.
+///
+/// # Edge cases
+/// * With --plain only the sift path is printed, after the plain description
+fn show_changes(path: &[usize], heap: &MenuHeap, options: &MenuOptions) {
+    let after = heap.as_slice();
+
+    if options.plain {
+        println!("{}", heap.describe_plain());
+    } else {
+        println!("Heap (d={}), changed slots in brackets", heap.d());
+        if after.is_empty() {
+            println!("Empty heap :(");
+        }
+        let mut start = 0;
+        let mut count = 1;
+        let mut level = 0;
+        while start < after.len() {
+            let end = after.len().min(start + count);
+            let cells: Vec<String> = (start..end)
+                .map(|i| {
+                    if path.contains(&i) {
+                        format!("[{}]", after[i])
+                    } else {
+                        after[i].to_string()
+                    }
+                })
+                .collect();
+            println!("Level {}: {}", level, cells.join(" "));
+            start = end;
+            count *= heap.d() as usize;
+            level += 1;
+        }
+    }
+
+    if !path.is_empty() {
+        let steps: Vec<String> = path
+            .iter()
+            .map(|&i| format!("slot {} ({})", i, after[i]))
+            .collect();
+        println!("Sift path: {}", steps.join(" -> "));
+    }
+}
+
+/// Appends an operation to the --trace file, if one was given. A failed
+/// write is reported but does not stop the session.
+fn trace_op(options: &MenuOptions, record: TraceRecord) {
+    if let Some(trace) = &options.trace {
+        if let Err(e) = trace.record(&record) {
+            println!("Failed to write trace: {}", e);
+        }
+    }
+}
+
+/// Builds the trace record for an operation on an existing heap.
+fn record_for<'a>(
+    op: &'a str,
+    input: &'a [i64],
+    heap: &'a MenuHeap,
+    sift_path: Option<&'a [usize]>,
+    error: Option<&'a str>,
+) -> TraceRecord<'a> {
+    TraceRecord {
+        op,
+        input,
+        d: heap.d(),
+        array: heap.as_slice(),
+        sift_path,
+        error,
+    }
+}
 
 fn get_user_input(prompt: &str) -> String {
     print!("{}", prompt);
@@ -26,12 +311,20 @@ fn display_menu() -> Option<u32> {
     println!("3. Extract Max");
     println!("4. Insert");
     println!("5. Print heap");
-    println!("6. Exit");
+    println!("6. Print sorted");
+    println!("7. Inspect node");
+    println!("8. Repair heap");
+    println!("9. Increase key");
+    println!("10. Decrease key");
+    println!("11. Delete node");
+    println!("12. Save heap");
+    println!("13. Load heap");
+    println!("14. Exit");
 
     get_number_input("Enter your choice: ")
 }
 
-fn build_heap() -> Option<Heap> {
+fn build_heap(options: &MenuOptions) -> Option<MenuHeap> {
     match get_number_input::<u32>("Enter D value: ") {
         Some(d) if d >= 2 => {
             let input = get_user_input("Enter numbers separated by spaces: ");
@@ -40,10 +333,52 @@ fn build_heap() -> Option<Heap> {
                 .filter_map(|s| s.parse().ok())
                 .collect();
 
-            let heap = Heap::new(d, &numbers);
-            println!("Heap built successfully!");
-            heap.print();
-            Some(heap)
+            let mut input = vec![i64::from(d)];
+            input.extend(numbers.iter().map(|&x| i64::from(x)));
+
+            let started = Instant::now();
+            match BoundedHeap::build(
+                d,
+                HEAP_MAX_SIZE,
+                &numbers,
+                options.order.priority(),
+                options.on_full,
+            ) {
+                Ok((heap, dropped)) => {
+                    trace_op(options, record_for("build", &input, &heap, None, None));
+                    let stats = OpStats {
+                        elapsed: started.elapsed(),
+                        sift_steps: heap.sift_steps(),
+                    };
+                    println!(
+                        "Heap built successfully! (order: {})",
+                        options.order.describe()
+                    );
+                    if !dropped.is_empty() {
+                        report_dropped(&heap, &dropped);
+                    }
+                    show_heap(&heap, options);
+                    show_handles(&heap);
+                    if options.stats {
+                        stats.print(&heap);
+                    }
+                    Some(heap)
+                }
+                Err(e) => {
+                    println!("Failed to build heap: {}", e);
+                    let error = e.to_string();
+                    let record = TraceRecord {
+                        op: "build",
+                        input: &input,
+                        d,
+                        array: &[],
+                        sift_path: None,
+                        error: Some(&error),
+                    };
+                    trace_op(options, record);
+                    None
+                }
+            }
         }
         Some(_) => {
             println!("D must be at least 2.");
@@ -56,89 +391,463 @@ fn build_heap() -> Option<Heap> {
     }
 }
 
-fn change_d(heap: &mut Heap) {
+/// Reports the values a full heap turned away or evicted during a build.
+fn report_dropped(heap: &MenuHeap, dropped: &[i32]) {
+    let verb = match heap.policy() {
+        OverflowPolicy::Reject => "rejected",
+        OverflowPolicy::EvictMin | OverflowPolicy::EvictOldest => "evicted",
+    };
+    let values: Vec<String> = dropped.iter().map(|x| x.to_string()).collect();
+    println!(
+        "Heap full ({} slots): {} {} values: {}",
+        heap.capacity(),
+        verb,
+        dropped.len(),
+        values.join(" ")
+    );
+}
+
+fn change_d(heap: &mut MenuHeap, options: &MenuOptions) {
     match get_number_input::<u32>("Enter new D value: ") {
-        Some(d) if d >= 1 => {
-            heap.change_d(d);
-            println!("D value changed successfully!");
-            println!("New heap: ");
-            heap.print()
-        }
+        Some(d) if d >= 1 => match measure(heap, |h| h.change_d(d)) {
+            (Ok(_), stats) => {
+                let input = [i64::from(d)];
+                trace_op(options, record_for("change_d", &input, heap, None, None));
+                println!("D value changed successfully!");
+                println!("New heap: ");
+                show_heap(heap, options);
+                if options.stats {
+                    stats.print(heap);
+                }
+            }
+            (Err(e), _) => {
+                println!("Failed to change D: {}", e);
+                let (input, error) = ([i64::from(d)], e.to_string());
+                trace_op(
+                    options,
+                    record_for("change_d", &input, heap, None, Some(&error)),
+                );
+            }
+        },
         Some(_) => println!("D must be at least 1."),
         None => println!("Invalid input for D."),
     }
 }
 
-fn extract_max(heap: &mut Heap) {
-    match heap.extract_max() {
-        Ok(max) => {
-            println!("Maximum value: {}", max);
+fn extract_max(heap: &mut MenuHeap, options: &MenuOptions) {
+    let before = heap.as_slice().to_vec();
+    match measure(heap, MenuHeap::extract_max) {
+        (Ok(max), stats) => {
+            let path = sift_path(&before, heap, false);
+            trace_op(
+                options,
+                record_for("extract_max", &[], heap, Some(&path), None),
+            );
+            println!("Extracted value: {}", max);
             println!("New heap: ");
-            heap.print()
+            show_changes(&path, heap, options);
+            if options.stats {
+                stats.print(heap);
+            }
+        }
+        (Err(e), _) => {
+            println!("Error extracting max: {}", e);
+            let error = e.to_string();
+            trace_op(
+                options,
+                record_for("extract_max", &[], heap, None, Some(&error)),
+            );
         }
-        Err(e) => println!("Error extracting max: {:?}", e),
     }
 }
 
-fn insert_value(heap: &mut Heap) {
+fn insert_value(heap: &mut MenuHeap, options: &MenuOptions) {
     match get_number_input::<i32>("Enter a number to insert: ") {
-        Some(num) => match heap.insert(num) {
-            Ok(_) => {
-                println!("Successfully inserted {}", num);
-                println!("New heap: ");
-                heap.print()
+        Some(num) => {
+            let before = heap.as_slice().to_vec();
+            let input = [i64::from(num)];
+            let outcome = measure(heap, |h| h.insert(num));
+            let path = sift_path(&before, heap, true);
+            match &outcome.0 {
+                Ok(_) => trace_op(
+                    options,
+                    record_for("insert", &input, heap, Some(&path), None),
+                ),
+                Err(e) => {
+                    let error = e.to_string();
+                    trace_op(
+                        options,
+                        record_for("insert", &input, heap, None, Some(&error)),
+                    );
+                }
             }
-            Err(e) => println!("Failed to insert: {:?}", e),
-        },
+            match outcome {
+                (Ok(Inserted { handle: None, .. }), _) => {
+                    println!("Heap full: {} ranks lowest, so it was dropped", num);
+                }
+                (
+                    Ok(Inserted {
+                        handle: Some(handle),
+                        evicted,
+                    }),
+                    stats,
+                ) => {
+                    println!("Successfully inserted {} (handle {})", num, handle);
+                    if let Some(evicted) = evicted {
+                        println!("Heap full: evicted {} to make room", evicted);
+                    }
+                    println!("New heap: ");
+                    show_changes(&path, heap, options);
+                    if options.stats {
+                        stats.print(heap);
+                    }
+                }
+                (Err(e), _) => println!("Failed to insert: {}", e),
+            }
+        }
         None => println!("Invalid number."),
     }
 }
 
-fn print_heap(heap: &Heap) {
-    heap.print();
+fn print_heap(heap: &MenuHeap, options: &MenuOptions) {
+    show_heap(heap, options);
 }
 
-fn main() {
-    let mut heap: Option<Heap> = None;
+fn print_sorted(heap: &MenuHeap) {
+    let sorted: Vec<String> = heap.sorted_iter().map(|x| x.to_string()).collect();
+    println!("Sorted: {}", sorted.join(" "));
+}
+
+fn inspect_node(heap: &MenuHeap) {
+    let Some(idx) = get_number_input::<usize>("Enter node index: ") else {
+        println!("Invalid index.");
+        return;
+    };
+
+    let (path, children) = match (heap.path_to_root(idx), heap.children(idx)) {
+        (Ok(path), Ok(children)) => (path, children),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("Failed to inspect node: {}", e);
+            return;
+        }
+    };
+
+    println!("Node {}: value {}", idx, path[0].1);
+    match path.get(1) {
+        Some((parent_idx, parent)) => println!("Parent: index {} (value {})", parent_idx, parent),
+        None => println!("Parent: none, this is the root"),
+    }
+    for (n, child) in children.iter().enumerate() {
+        match child {
+            Some((child_idx, value)) => {
+                println!("Child {}: index {} (value {})", n, child_idx, value)
+            }
+            None => println!("Child {}: absent", n),
+        }
+    }
+}
+
+fn repair_heap(heap: &mut MenuHeap, options: &MenuOptions) {
+    let outcome = measure(heap, MenuHeap::repair);
+    let error = outcome.0.as_ref().err().map(ToString::to_string);
+    trace_op(
+        options,
+        record_for("repair", &[], heap, None, error.as_deref()),
+    );
+    match outcome {
+        (Ok(0), _) => println!("No heap-property violations found."),
+        (Ok(found), stats) => {
+            println!("Repaired {} heap-property violations.", found);
+            println!("New heap: ");
+            show_heap(heap, options);
+            if options.stats {
+                stats.print(heap);
+            }
+        }
+        (Err(e), _) => println!("Failed to repair heap: {}", e),
+    }
+}
+
+/// Prints every element's handle, in arrival order, as taken by Increase
+/// key, Decrease key and Delete node.
+fn show_handles(heap: &MenuHeap) {
+    let mut handles: Vec<(Handle, &i32)> = heap.handles().collect();
+    handles.sort();
+    let handles: Vec<String> = handles
+        .iter()
+        .map(|(handle, value)| format!("{}:{}", handle, value))
+        .collect();
+    println!("Handles: {}", handles.join(" "));
+}
+
+/// Reads a handle printed by Insert or after a build, as `slot.generation`.
+fn get_handle_input() -> Option<Handle> {
+    get_number_input("Enter handle (slot.generation): ")
+}
+
+/// Trace input for a handle: its slot, then its generation.
+fn handle_input(handle: Handle) -> [i64; 2] {
+    [i64::from(handle.slot()), i64::from(handle.generation())]
+}
+
+/// Changes the value of the element with the given handle, sifting it up
+/// for an increase and down for a decrease.
+fn change_key(heap: &mut MenuHeap, options: &MenuOptions, increase: bool) {
+    let Some(handle) = get_handle_input() else {
+        println!("Invalid handle.");
+        return;
+    };
+    let Some(value) = get_number_input::<i32>("Enter new value: ") else {
+        println!("Invalid number.");
+        return;
+    };
+
+    let (op, upward) = if increase {
+        ("increase_key", true)
+    } else {
+        ("decrease_key", false)
+    };
+    let [slot, generation] = handle_input(handle);
+    let input = [slot, generation, i64::from(value)];
+    let before = heap.as_slice().to_vec();
+    let outcome = measure(heap, |h| {
+        if increase {
+            h.increase_key(handle, value)
+        } else {
+            h.decrease_key(handle, value)
+        }
+    });
+    match outcome {
+        (Ok(old), stats) => {
+            let path = sift_path(&before, heap, upward);
+            trace_op(options, record_for(op, &input, heap, Some(&path), None));
+            println!("Changed handle {} from {} to {}", handle, old, value);
+            println!("New heap: ");
+            show_changes(&path, heap, options);
+            if options.stats {
+                stats.print(heap);
+            }
+        }
+        (Err(e), _) => {
+            println!("Failed to change key: {}", e);
+            let error = e.to_string();
+            trace_op(options, record_for(op, &input, heap, None, Some(&error)));
+        }
+    }
+}
+
+fn delete_node(heap: &mut MenuHeap, options: &MenuOptions) {
+    let Some(handle) = get_handle_input() else {
+        println!("Invalid handle.");
+        return;
+    };
+
+    let input = handle_input(handle);
+    let before = heap.as_slice().to_vec();
+    match measure(heap, |h| h.delete(handle)) {
+        (Ok(removed), stats) => {
+            let path = sift_path(&before, heap, false);
+            trace_op(
+                options,
+                record_for("delete", &input, heap, Some(&path), None),
+            );
+            println!("Deleted handle {} (value {})", handle, removed);
+            println!("New heap: ");
+            show_changes(&path, heap, options);
+            if options.stats {
+                stats.print(heap);
+            }
+        }
+        (Err(e), _) => {
+            println!("Failed to delete node: {}", e);
+            let error = e.to_string();
+            trace_op(
+                options,
+                record_for("delete", &input, heap, None, Some(&error)),
+            );
+        }
+    }
+}
+
+fn save_heap(heap: &MenuHeap, options: &MenuOptions) {
+    let path = get_user_input("Enter file to save to: ");
+    match heap.save_to(&path) {
+        Ok(()) => {
+            trace_op(options, record_for("save", &[], heap, None, None));
+            println!("Saved {} elements (d={}) to {}", heap.len(), heap.d(), path);
+        }
+        Err(e) => {
+            println!("Failed to save heap: {}", e);
+            let error = e.to_string();
+            trace_op(options, record_for("save", &[], heap, None, Some(&error)));
+        }
+    }
+}
+
+/// Loads a heap written by Save heap, under the session's --order and
+/// --on-full, replacing the current heap only if the load succeeds.
+///
+/// # Edge cases
+/// * A failed load is traced against `current`, the heap that stays
+fn load_heap(options: &MenuOptions, current: Option<&MenuHeap>) -> Option<MenuHeap> {
+    let path = get_user_input("Enter file to load from: ");
+    let loaded = BoundedHeap::load_bounded(
+        &path,
+        HEAP_MAX_SIZE,
+        options.order.priority(),
+        options.on_full,
+    );
+    match loaded {
+        Ok((heap, dropped)) => {
+            trace_op(options, record_for("load", &[], &heap, None, None));
+            println!(
+                "Loaded {} elements (d={}) from {}",
+                heap.len(),
+                heap.d(),
+                path
+            );
+            if !dropped.is_empty() {
+                report_dropped(&heap, &dropped);
+            }
+            show_heap(&heap, options);
+            show_handles(&heap);
+            Some(heap)
+        }
+        Err(e) => {
+            println!("Failed to load heap: {}", e);
+            let error = e.to_string();
+            let record = match current {
+                Some(heap) => record_for("load", &[], heap, None, Some(&error)),
+                None => TraceRecord {
+                    op: "load",
+                    input: &[],
+                    d: 0,
+                    array: &[],
+                    sift_path: None,
+                    error: Some(&error),
+                },
+            };
+            trace_op(options, record);
+            None
+        }
+    }
+}
+
+fn run_interactive(options: &MenuOptions) {
+    let mut heap: Option<MenuHeap> = None;
 
     loop {
         match display_menu() {
             Some(1) => {
-                heap = build_heap();
+                heap = build_heap(options);
             }
             Some(2) => {
                 if let Some(ref mut h) = heap {
-                    change_d(h);
+                    change_d(h, options);
                 } else {
                     println!("No heap exists. Please build a heap first.");
                 }
             }
             Some(3) => {
                 if let Some(ref mut h) = heap {
-                    extract_max(h);
+                    extract_max(h, options);
                 } else {
                     println!("No heap exists. Please build a heap first.");
                 }
             }
             Some(4) => {
                 if let Some(ref mut h) = heap {
-                    insert_value(h);
+                    insert_value(h, options);
                 } else {
                     println!("No heap exists. Please build a heap first.");
                 }
             }
             Some(5) => {
                 if let Some(ref h) = heap {
-                    print_heap(h);
+                    print_heap(h, options);
                 } else {
                     println!("No heap exists. Please build a heap first.");
                 }
             }
             Some(6) => {
+                if let Some(ref h) = heap {
+                    print_sorted(h);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(7) => {
+                if let Some(ref h) = heap {
+                    inspect_node(h);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(8) => {
+                if let Some(ref mut h) = heap {
+                    repair_heap(h, options);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(9) => {
+                if let Some(ref mut h) = heap {
+                    change_key(h, options, true);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(10) => {
+                if let Some(ref mut h) = heap {
+                    change_key(h, options, false);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(11) => {
+                if let Some(ref mut h) = heap {
+                    delete_node(h, options);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(12) => {
+                if let Some(ref h) = heap {
+                    save_heap(h, options);
+                } else {
+                    println!("No heap exists. Please build a heap first.");
+                }
+            }
+            Some(13) => {
+                if let Some(loaded) = load_heap(options, heap.as_ref()) {
+                    heap = Some(loaded);
+                }
+            }
+            Some(14) => {
                 println!("Exiting...");
                 break;
             }
-            _ => println!("Invalid choice. Please enter a number between 1 and 6."),
+            _ => println!("Invalid choice. Please enter a number between 1 and 14."),
         }
     }
 }
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("batch" | "--batch") => batch::run(&args[1..]),
+        Some("demo") => demo::run(&args[1..]),
+        Some("extsort") => extsort::run(&args[1..]),
+        Some("select") => select::run(&args[1..]),
+        Some("soak") => soak::run(&args[1..]),
+        Some("stress") => stress::run(&args[1..]),
+        Some(other) if !other.starts_with("--") => Err(format!("Unknown command: {}", other)),
+        _ => parse_menu_options(&args).map(|options| run_interactive(&options)),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
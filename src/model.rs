@@ -0,0 +1,332 @@
+use crate::handles::Handle;
+use crate::heap::{Heap, HeapError, HEAP_MAX_SIZE};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One operation applied to both a heap and the model.
+#[derive(Debug, Clone)]
+pub enum Op {
+    Insert(i32),
+    ExtractMax,
+    ChangeD(u32),
+    /// Raises the handle's element to the given value.
+    IncreaseKey(Handle, i32),
+    /// Lowers the handle's element to the given value.
+    DecreaseKey(Handle, i32),
+    Delete(Handle),
+}
+
+/// Reference max-priority queue the heap is checked against, capped at
+/// HEAP_MAX_SIZE, so it matches a heap made with
+/// `Heap::bounded(d, HEAP_MAX_SIZE, ..)`.
+///
+/// Elements are filed under the handles the heap issued for them, so key
+/// changes and deletes can be mirrored, and kept in a
+/// `std::collections::BTreeSet` ordered by value. `BinaryHeap` can't find
+/// an element by handle, so it only checks the value-only operations, in
+/// this module's tests.
+#[derive(Default)]
+pub struct Model {
+    values: BTreeMap<Handle, i32>,
+    order: BTreeSet<(i32, Handle)>,
+    /// The latest generation that left the heap, per slot, so handles at or
+    /// below it are stale rather than unknown.
+    retired: BTreeMap<u32, u32>,
+}
+
+impl Model {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mirrors Heap::insert on a bounded heap, including HeapFull at
+    /// HEAP_MAX_SIZE. The item is filed under `handle`, the handle the heap
+    /// returned; with None it is not kept.
+    pub fn insert(&mut self, item: i32, handle: Option<Handle>) -> Result<(), HeapError> {
+        if self.values.len() >= HEAP_MAX_SIZE {
+            return Err(HeapError::HeapFull {
+                capacity: HEAP_MAX_SIZE,
+            });
+        }
+        if let Some(handle) = handle {
+            self.values.insert(handle, item);
+            self.order.insert((item, handle));
+        }
+        Ok(())
+    }
+
+    /// Mirrors Heap::extract_max, including EmptyHeap.
+    ///
+    /// # Edge cases
+    /// * Among equal largest values, `root` is removed if it is one of
+    ///   them, so the model forgets the same handle the heap did
+    pub fn extract_max(&mut self, root: Option<Handle>) -> Result<i32, HeapError> {
+        let &(max, last) = self.order.last().ok_or(HeapError::EmptyHeap)?;
+        let handle = root
+            .filter(|root| self.values.get(root) == Some(&max))
+            .unwrap_or(last);
+        self.delete(handle)
+    }
+
+    /// Mirrors Heap::increase_key, including StaleHandle, UnknownHandle and
+    /// InvalidKeyChange.
+    pub fn increase_key(&mut self, handle: Handle, value: i32) -> Result<i32, HeapError> {
+        self.change_key(handle, value, |old| value < old)
+    }
+
+    /// Mirrors Heap::decrease_key, including StaleHandle, UnknownHandle and
+    /// InvalidKeyChange.
+    pub fn decrease_key(&mut self, handle: Handle, value: i32) -> Result<i32, HeapError> {
+        self.change_key(handle, value, |old| value > old)
+    }
+
+    /// Mirrors Heap::delete, including StaleHandle and UnknownHandle.
+    pub fn delete(&mut self, handle: Handle) -> Result<i32, HeapError> {
+        let value = self
+            .values
+            .remove(&handle)
+            .ok_or_else(|| self.missing(handle))?;
+        self.order.remove(&(value, handle));
+        self.retired.insert(handle.slot(), handle.generation());
+        Ok(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn change_key(
+        &mut self,
+        handle: Handle,
+        value: i32,
+        wrong_way: impl Fn(i32) -> bool,
+    ) -> Result<i32, HeapError> {
+        let old = *self
+            .values
+            .get(&handle)
+            .ok_or_else(|| self.missing(handle))?;
+        if wrong_way(old) {
+            return Err(HeapError::InvalidKeyChange { handle });
+        }
+        self.order.remove(&(old, handle));
+        self.order.insert((value, handle));
+        self.values.insert(handle, value);
+        Ok(old)
+    }
+
+    /// The error the heap gives for a handle the model doesn't hold.
+    fn missing(&self, handle: Handle) -> HeapError {
+        match self.retired.get(&handle.slot()) {
+            Some(&generation) if handle.generation() <= generation => {
+                HeapError::StaleHandle { handle }
+            }
+            _ => HeapError::UnknownHandle { handle },
+        }
+    }
+}
+
+/// Applies the operation to both the heap and the model and checks they
+/// agree on the result and on the size afterwards.
+///
+/// # Edge cases
+/// * ChangeD has no model counterpart; it only has to succeed and keep the size
+pub fn check_op(heap: &mut Heap, model: &mut Model, op: &Op) -> Result<(), String> {
+    match *op {
+        Op::Insert(item) => {
+            let got = heap.insert(item);
+            let expected = model.insert(item, got.as_ref().ok().copied());
+            let got = got.map(|_| ());
+            if got != expected {
+                return Err(format!(
+                    "insert({}) returned {:?}, model returned {:?}",
+                    item, got, expected
+                ));
+            }
+        }
+        Op::ExtractMax => {
+            let root = heap.handle_at(0).ok();
+            let (got, expected) = (heap.extract_max(), model.extract_max(root));
+            if got != expected {
+                return Err(format!(
+                    "extract_max returned {:?}, model returned {:?}",
+                    got, expected
+                ));
+            }
+        }
+        Op::ChangeD(d) => heap
+            .change_d(d)
+            .map_err(|e| format!("change_d failed: {}", e))?,
+        Op::IncreaseKey(handle, value) => {
+            let (got, expected) = (
+                heap.increase_key(handle, value),
+                model.increase_key(handle, value),
+            );
+            if got != expected {
+                return Err(format!(
+                    "increase_key({}, {}) returned {:?}, model returned {:?}",
+                    handle, value, got, expected
+                ));
+            }
+        }
+        Op::DecreaseKey(handle, value) => {
+            let (got, expected) = (
+                heap.decrease_key(handle, value),
+                model.decrease_key(handle, value),
+            );
+            if got != expected {
+                return Err(format!(
+                    "decrease_key({}, {}) returned {:?}, model returned {:?}",
+                    handle, value, got, expected
+                ));
+            }
+        }
+        Op::Delete(handle) => {
+            let (got, expected) = (heap.delete(handle), model.delete(handle));
+            if got != expected {
+                return Err(format!(
+                    "delete({}) returned {:?}, model returned {:?}",
+                    handle, got, expected
+                ));
+            }
+        }
+    }
+
+    if heap.len() != model.len() {
+        return Err(format!(
+            "size mismatch: heap has {}, model has {}",
+            heap.len(),
+            model.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MaxOrder;
+    use proptest::prelude::*;
+    use std::collections::BinaryHeap;
+
+    /// An operation whose target, if any, is picked from the heap's live
+    /// handles once the heap it runs against is known.
+    #[derive(Debug, Clone)]
+    enum Step {
+        Plain(Op),
+        IncreaseKey(usize, i32),
+        DecreaseKey(usize, i32),
+        Delete(usize),
+    }
+
+    /// Returns the handle of an element that has left a drained heap. Every
+    /// heap issues the same first handle, so it is stale in a drained heap
+    /// that has held an element and was never issued by one that hasn't.
+    fn drained_handle() -> Handle {
+        let mut heap = Heap::new(2, &[]).unwrap();
+        let handle = heap.insert(0).unwrap();
+        heap.delete(handle).unwrap();
+        handle
+    }
+
+    /// Picks the `pick`th live handle, wrapping around; on an empty heap
+    /// it falls back to drained_handle, so StaleHandle and UnknownHandle
+    /// are exercised too.
+    fn pick_handle(heap: &Heap, pick: usize) -> Handle {
+        match heap.len() {
+            0 => drained_handle(),
+            len => heap.handle_at(pick % len).unwrap(),
+        }
+    }
+
+    fn resolve(step: &Step, heap: &Heap) -> Op {
+        let value_of = |handle| heap.get(handle).copied().unwrap_or(0);
+        match *step {
+            Step::Plain(ref op) => op.clone(),
+            Step::IncreaseKey(pick, by) => {
+                let handle = pick_handle(heap, pick);
+                Op::IncreaseKey(handle, value_of(handle).saturating_add(by))
+            }
+            Step::DecreaseKey(pick, by) => {
+                let handle = pick_handle(heap, pick);
+                Op::DecreaseKey(handle, value_of(handle).saturating_sub(by))
+            }
+            Step::Delete(pick) => Op::Delete(pick_handle(heap, pick)),
+        }
+    }
+
+    fn step_strategy() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            5 => any::<i32>().prop_map(|x| Step::Plain(Op::Insert(x))),
+            4 => Just(Step::Plain(Op::ExtractMax)),
+            1 => (1u32..=10).prop_map(|d| Step::Plain(Op::ChangeD(d))),
+            1 => (any::<usize>(), -10i32..1000).prop_map(|(pick, by)| Step::IncreaseKey(pick, by)),
+            1 => (any::<usize>(), -10i32..1000).prop_map(|(pick, by)| Step::DecreaseKey(pick, by)),
+            1 => any::<usize>().prop_map(Step::Delete),
+        ]
+    }
+
+    fn plain_op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            5 => any::<i32>().prop_map(Op::Insert),
+            4 => Just(Op::ExtractMax),
+            1 => (1u32..=10).prop_map(Op::ChangeD),
+        ]
+    }
+
+    proptest! {
+        /// Checks the value-only operations against std's BinaryHeap, with
+        /// no handles involved, as a second oracle next to Model.
+        #[test]
+        fn test_matches_binary_heap(d in 2u32..=10, ops in prop::collection::vec(plain_op_strategy(), 0..1500)) {
+            let mut heap = Heap::bounded(d, HEAP_MAX_SIZE, &[], MaxOrder).unwrap();
+            let mut oracle = BinaryHeap::new();
+            for op in &ops {
+                match *op {
+                    Op::Insert(item) => {
+                        let expected = if oracle.len() < HEAP_MAX_SIZE {
+                            oracle.push(item);
+                            Ok(())
+                        } else {
+                            Err(HeapError::HeapFull { capacity: HEAP_MAX_SIZE })
+                        };
+                        prop_assert_eq!(heap.insert(item).map(|_| ()), expected);
+                    }
+                    Op::ExtractMax => {
+                        prop_assert_eq!(heap.extract_max(), oracle.pop().ok_or(HeapError::EmptyHeap));
+                    }
+                    Op::ChangeD(d) => prop_assert_eq!(heap.change_d(d), Ok(())),
+                    _ => unreachable!("plain_op_strategy makes no handle operations"),
+                }
+                prop_assert_eq!(heap.len(), oracle.len());
+            }
+            prop_assert!(heap.is_heap());
+            prop_assert_eq!(BinaryHeap::from(heap).into_sorted_vec(), oracle.into_sorted_vec());
+        }
+
+        #[test]
+        fn test_matches_reference_model(d in 2u32..=10, steps in prop::collection::vec(step_strategy(), 0..1500)) {
+            let mut heap = Heap::bounded(d, HEAP_MAX_SIZE, &[], MaxOrder).unwrap();
+            let mut model = Model::new();
+            for step in &steps {
+                let op = resolve(step, &heap);
+                prop_assert_eq!(check_op(&mut heap, &mut model, &op), Ok(()));
+            }
+            prop_assert!(heap.is_heap());
+            while !model.is_empty() {
+                prop_assert_eq!(check_op(&mut heap, &mut model, &Op::ExtractMax), Ok(()));
+            }
+            prop_assert!(heap.is_empty());
+        }
+
+        #[test]
+        fn test_build_matches_sort(d in 1u32..=10, items in prop::collection::vec(any::<i32>(), 0..HEAP_MAX_SIZE)) {
+            let heap = Heap::new(d, &items).unwrap();
+            let mut sorted = items.clone();
+            sorted.sort_unstable_by(|a, b| b.cmp(a));
+            prop_assert_eq!(heap.sorted_iter().copied().collect::<Vec<i32>>(), sorted);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::heap::{Heap, HeapError, HEAP_MAX_SIZE};
+use crate::priority::Priority;
+use crate::rng::Rng;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Hands each thread a different seed for its sampling generator.
+static NEXT_SEED: AtomicU64 = AtomicU64::new(0x2545_F491_4F6C_DD1D);
+
+thread_local! {
+    static THREAD_RNG: RefCell<Rng> =
+        RefCell::new(Rng::new(NEXT_SEED.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)));
+}
+
+fn random_below(bound: usize) -> usize {
+    THREAD_RNG.with(|rng| rng.borrow_mut().below(bound as u64) as usize)
+}
+
+/// A relaxed priority queue for parallel consumers, after the MultiQueue design.
+///
+/// Items go into a random internal heap. `pop` samples a few heaps, compares
+/// their roots and extracts from the best one, so it returns an item close to
+/// the global best while threads rarely contend for the same lock.
+pub struct MultiQueue<P: Priority + Clone> {
+    queues: Vec<Mutex<Heap<i32, P>>>,
+    priority: P,
+    samples: usize,
+}
+
+impl<P: Priority + Clone> MultiQueue<P> {
+    /// Creates `queues` empty d-ary heaps of HEAP_MAX_SIZE items each,
+    /// sampling `samples` of them per pop.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * Queue and sample counts of 0 are treated as 1, and samples are capped
+    ///   at the number of queues
+    pub fn new(queues: usize, samples: usize, d: u32, priority: P) -> Result<Self, HeapError> {
+        let queues = (0..queues.max(1))
+            .map(|_| Heap::bounded(d, HEAP_MAX_SIZE, &[], priority.clone()).map(Mutex::new))
+            .collect::<Result<Vec<_>, HeapError>>()?;
+        let samples = samples.clamp(1, queues.len());
+        Ok(Self {
+            queues,
+            priority,
+            samples,
+        })
+    }
+
+    fn lock(&self, queue: usize) -> MutexGuard<'_, Heap<i32, P>> {
+        self.queues[queue].lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Inserts the item into a random queue, skipping queues that are locked
+    /// by another thread on the first pass.
+    ///
+    /// # Edge cases
+    /// * Returns HeapFull error with the total capacity if every queue is full
+    pub fn push(&self, item: i32) -> Result<(), HeapError> {
+        let start = random_below(self.queues.len());
+        for offset in 0..self.queues.len() {
+            let queue = (start + offset) % self.queues.len();
+            if let Ok(mut heap) = self.queues[queue].try_lock() {
+                match heap.insert(item) {
+                    Err(HeapError::HeapFull { .. }) => continue,
+                    result => return result.map(|_| ()),
+                }
+            }
+        }
+
+        // Every queue was busy or full; wait for locks this time
+        for offset in 0..self.queues.len() {
+            let queue = (start + offset) % self.queues.len();
+            match self.lock(queue).insert(item) {
+                Err(HeapError::HeapFull { .. }) => continue,
+                result => return result.map(|_| ()),
+            }
+        }
+
+        let capacity = self.queues.len() * HEAP_MAX_SIZE;
+        Err(HeapError::HeapFull { capacity })
+    }
+
+    /// Removes and returns the best root among `samples` distinct, randomly
+    /// chosen queues; with samples equal to the queue count it is the true best.
+    ///
+    /// # Edge cases
+    /// * If every sampled queue is empty, all queues are scanned, so None means
+    ///   the whole structure was empty when scanned
+    pub fn pop(&self) -> Option<i32> {
+        if let Some(item) = self.pop_best_of(&self.sample()) {
+            return Some(item);
+        }
+
+        let all: Vec<usize> = (0..self.queues.len()).collect();
+        loop {
+            let mut any_left = false;
+            for &queue in &all {
+                any_left |= !self.lock(queue).is_empty();
+            }
+            if !any_left {
+                return None;
+            }
+            if let Some(item) = self.pop_best_of(&all) {
+                return Some(item);
+            }
+        }
+    }
+
+    /// Picks `samples` distinct queues with a partial Fisher-Yates shuffle,
+    /// so sampling every queue compares every root.
+    fn sample(&self) -> Vec<usize> {
+        let mut queues: Vec<usize> = (0..self.queues.len()).collect();
+        for i in 0..self.samples {
+            let j = i + random_below(queues.len() - i);
+            queues.swap(i, j);
+        }
+        queues.truncate(self.samples);
+        queues
+    }
+
+    /// Extracts from whichever of the given queues has the best root.
+    fn pop_best_of(&self, queues: &[usize]) -> Option<i32> {
+        let mut best: Option<(usize, i32)> = None;
+        for &queue in queues {
+            if let Ok(&root) = self.lock(queue).peek_max() {
+                match best {
+                    Some((_, best_root)) if !self.priority.higher_than(&root, &best_root) => {}
+                    _ => best = Some((queue, root)),
+                }
+            }
+        }
+
+        let (queue, _) = best?;
+        self.lock(queue).extract_max().ok()
+    }
+
+    /// Returns the total number of items across all queues.
+    pub fn len(&self) -> usize {
+        (0..self.queues.len())
+            .map(|queue| self.lock(queue).len())
+            .sum()
+    }
+
+    /// Returns true if every queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MaxOrder;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_pops_everything_once() {
+        let queue = Arc::new(MultiQueue::new(8, 2, 4, MaxOrder).unwrap());
+        let producers: Vec<_> = (0..4)
+            .map(|t| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for i in 0..250 {
+                        queue.push(t * 250 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for p in producers {
+            p.join().unwrap();
+        }
+        assert_eq!(queue.len(), 1000);
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    while let Some(x) = queue.pop() {
+                        popped.push(x);
+                    }
+                    popped
+                })
+            })
+            .collect();
+
+        let mut all: Vec<i32> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..1000).collect::<Vec<i32>>());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_samples_are_distinct() {
+        let queue = MultiQueue::new(5, 3, 2, MaxOrder).unwrap();
+        for _ in 0..200 {
+            let mut sampled = queue.sample();
+            sampled.sort_unstable();
+            sampled.dedup();
+            assert_eq!(sampled.len(), 3);
+            assert!(sampled.iter().all(|&q| q < 5));
+        }
+        let all = MultiQueue::new(4, 4, 2, MaxOrder).unwrap();
+        let mut sampled = all.sample();
+        sampled.sort_unstable();
+        assert_eq!(sampled, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sampling_all_queues_is_exact() {
+        let queue = MultiQueue::new(3, 3, 2, MaxOrder).unwrap();
+        for x in [4, 8, 1, 9, 2, 7] {
+            assert!(queue.push(x).is_ok());
+        }
+        let out: Vec<i32> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(out, vec![9, 8, 7, 4, 2, 1]);
+
+        // Whatever queues the pushes land in, every pop compares all roots
+        for _ in 0..50 {
+            for x in 0..30 {
+                queue.push((x * 7) % 30).unwrap();
+            }
+            let out: Vec<i32> = std::iter::from_fn(|| queue.pop()).collect();
+            assert_eq!(out, (0..30).rev().collect::<Vec<i32>>());
+        }
+    }
+}
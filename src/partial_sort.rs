@@ -0,0 +1,199 @@
+use crate::heap::HeapError;
+
+/// Work done by a sort, counted for comparing strategies empirically.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SortStats {
+    /// Comparisons between two items.
+    pub comparisons: u64,
+    /// Swaps of two items.
+    pub swaps: u64,
+}
+
+/// Rearranges the slice so its first k positions hold the k smallest items in
+/// ascending order, like C++ `std::partial_sort`. The order of the remaining
+/// items is unspecified.
+///
+/// Keeps a d-ary max-heap of the k smallest items seen so far in `slice[..k]`,
+/// then heap-sorts that region, for O(n log k) overall.
+///
+/// # Edge cases
+/// * Returns InvalidD error if d is 0
+/// * If k exceeds the slice length, the whole slice is sorted
+pub fn partial_sort(slice: &mut [i32], k: usize, d: u32) -> Result<(), HeapError> {
+    partial_sort_with_stats(slice, k, d).map(|_| ())
+}
+
+/// Same as partial_sort, also returning how many comparisons and swaps it made.
+///
+/// # Edge cases
+/// * Returns InvalidD error if d is 0
+pub fn partial_sort_with_stats(
+    slice: &mut [i32],
+    k: usize,
+    d: u32,
+) -> Result<SortStats, HeapError> {
+    let mut stats = SortStats::default();
+    if d == 0 {
+        return Err(HeapError::InvalidD { d });
+    }
+
+    let d = d as usize;
+    let k = std::cmp::min(k, slice.len());
+    if k == 0 {
+        return Ok(stats);
+    }
+
+    for i in (0..=(k - 1) / d).rev() {
+        sift_down(&mut slice[..k], i, d, &mut stats);
+    }
+
+    for i in k..slice.len() {
+        stats.comparisons += 1;
+        if slice[i] < slice[0] {
+            slice.swap(0, i);
+            stats.swaps += 1;
+            sift_down(&mut slice[..k], 0, d, &mut stats);
+        }
+    }
+
+    for end in (1..k).rev() {
+        slice.swap(0, end);
+        stats.swaps += 1;
+        sift_down(&mut slice[..end], 0, d, &mut stats);
+    }
+
+    Ok(stats)
+}
+
+/// Moves the item at idx down the d-ary max-heap stored in `heap` until no son is larger.
+fn sift_down(heap: &mut [i32], mut idx: usize, d: usize, stats: &mut SortStats) {
+    loop {
+        let first_son = idx * d + 1;
+        if first_son >= heap.len() {
+            return;
+        }
+
+        let mut largest_idx = idx;
+        let last_son = std::cmp::min(first_son + d, heap.len());
+        for son_idx in first_son..last_son {
+            stats.comparisons += 1;
+            if heap[son_idx] > heap[largest_idx] {
+                largest_idx = son_idx;
+            }
+        }
+
+        if largest_idx == idx {
+            return;
+        }
+        heap.swap(largest_idx, idx);
+        stats.swaps += 1;
+        idx = largest_idx;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_partial_sort() {
+        for d in 1..6 {
+            let mut values = [9, 3, 7, 1, 8, 2, 2, 6, 5, 4];
+            assert!(partial_sort(&mut values, 4, d).is_ok());
+            assert_eq!(&values[..4], &[1, 2, 2, 3]);
+
+            let mut rest = values[4..].to_vec();
+            rest.sort_unstable();
+            assert_eq!(rest, vec![4, 5, 6, 7, 8, 9]);
+        }
+    }
+
+    #[test]
+    fn test_partial_sort_edge_cases() {
+        let mut values = [3, 1, 2];
+        assert!(partial_sort(&mut values, 10, 2).is_ok());
+        assert_eq!(values, [1, 2, 3]);
+
+        let mut values = [3, 1, 2];
+        assert!(partial_sort(&mut values, 0, 2).is_ok());
+        assert_eq!(values, [3, 1, 2]);
+
+        let mut empty: [i32; 0] = [];
+        assert!(partial_sort(&mut empty, 3, 2).is_ok());
+        assert_eq!(
+            partial_sort(&mut values, 2, 0),
+            Err(HeapError::InvalidD { d: 0 })
+        );
+    }
+
+    #[test]
+    fn test_partial_sort_stats() {
+        let mut sorted: Vec<i32> = (0..100).collect();
+        let stats = partial_sort_with_stats(&mut sorted, 1, 2).unwrap();
+        // k = 1: one comparison per remaining item, nothing ever smaller
+        assert_eq!(
+            stats,
+            SortStats {
+                comparisons: 99,
+                swaps: 0
+            }
+        );
+
+        let values = [9, 3, 7, 1, 8, 2, 2, 6, 5, 4];
+        let counts: Vec<(u32, SortStats)> = (2..=4)
+            .map(|d| {
+                let mut sorted = values;
+                let stats = partial_sort_with_stats(&mut sorted, 10, d).unwrap();
+                assert_eq!(sorted, [1, 2, 2, 3, 4, 5, 6, 7, 8, 9]);
+                (d, stats)
+            })
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                (
+                    2,
+                    SortStats {
+                        comparisons: 37,
+                        swaps: 23
+                    }
+                ),
+                (
+                    3,
+                    SortStats {
+                        comparisons: 35,
+                        swaps: 19
+                    }
+                ),
+                (
+                    4,
+                    SortStats {
+                        comparisons: 37,
+                        swaps: 17
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_sort_matches_sort() {
+        let mut rng = Rng::new(3);
+        for d in 1..=8 {
+            for _ in 0..20 {
+                let len = rng.below(200) as usize;
+                let k = rng.below(len as u64 + 10) as usize;
+                let mut values: Vec<i32> = (0..len).map(|_| rng.range_i32(-50, 50)).collect();
+                let mut expected = values.clone();
+                expected.sort_unstable();
+
+                partial_sort(&mut values, k, d).unwrap();
+                let k = k.min(len);
+                assert_eq!(&values[..k], &expected[..k]);
+                values[k..].sort_unstable();
+                assert_eq!(values, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::bounded::{BoundedHeap, OverflowPolicy};
+use crate::heap::{Heap, HeapError};
+use crate::priority::{MaxOrder, Priority};
+use std::fs;
+use std::path::Path;
+
+/// First line of every saved heap, so other files are rejected up front.
+const HEADER: &str = "d-heap v1";
+
+/// Heaps are saved as four lines of text:
+///
+/// ```text
+/// d-heap v1
+/// d 3
+/// size 5
+/// 9 7 8 3 5
+/// ```
+///
+/// with the elements in storage order, so a heap loaded back under the same
+/// order needs no rebuild.
+impl<P: Priority> Heap<i32, P> {
+    /// Writes d, the size and the elements to `path`, replacing the file.
+    ///
+    /// # Edge cases
+    /// * Returns Io error with the error kind if the file can't be written
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), HeapError> {
+        let items: Vec<String> = self.as_slice().iter().map(|x| x.to_string()).collect();
+        let text = format!(
+            "{}\nd {}\nsize {}\n{}\n",
+            HEADER,
+            self.d(),
+            self.len(),
+            items.join(" ")
+        );
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Reads a heap written by save_to, ordered by `priority`.
+    ///
+    /// # Edge cases
+    /// * Returns Io error with the error kind if the file can't be read
+    /// * Returns InvalidFile error with the first bad line if the header,
+    ///   d, the size or an element doesn't parse, the size doesn't match
+    ///   the number of elements, or anything follows the element line
+    /// * Returns InvalidD error if the saved d is 0
+    /// * A file saved under another order is rebuilt rather than rejected
+    pub fn load_with_priority(path: impl AsRef<Path>, priority: P) -> Result<Self, HeapError> {
+        let text = fs::read_to_string(path)?;
+        let (d, items) = parse(&text)?;
+        Heap::with_priority(d, &items, priority)
+    }
+}
+
+impl Heap {
+    /// Reads a max-heap written by save_to; see load_with_priority.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, HeapError> {
+        Self::load_with_priority(path, MaxOrder)
+    }
+}
+
+impl<P: Priority> BoundedHeap<P> {
+    /// Reads a heap written by save_to into a BoundedHeap of `capacity`,
+    /// building it once; see BoundedHeap::build for what is returned.
+    ///
+    /// # Edge cases
+    /// * Fails as load_with_priority does
+    /// * Elements beyond capacity go through `policy` in storage order
+    pub fn load_bounded(
+        path: impl AsRef<Path>,
+        capacity: usize,
+        priority: P,
+        policy: OverflowPolicy,
+    ) -> Result<(Self, Vec<i32>), HeapError> {
+        let text = fs::read_to_string(path)?;
+        let (d, items) = parse(&text)?;
+        Self::build(d, capacity, &items, priority, policy)
+    }
+}
+
+/// Parses the saved text into d and the elements.
+fn parse(text: &str) -> Result<(u32, Vec<i32>), HeapError> {
+    let mut lines = text.lines();
+    let mut next_line = |line: usize| lines.next().ok_or(HeapError::InvalidFile { line });
+
+    if next_line(1)? != HEADER {
+        return Err(HeapError::InvalidFile { line: 1 });
+    }
+    let d: u32 = field(next_line(2)?, "d", 2)?;
+    let size: usize = field(next_line(3)?, "size", 3)?;
+    let items = next_line(4).unwrap_or("");
+    let items: Vec<i32> = items
+        .split_whitespace()
+        .map(|x| x.parse().map_err(|_| HeapError::InvalidFile { line: 4 }))
+        .collect::<Result<_, _>>()?;
+
+    if items.len() != size {
+        return Err(HeapError::InvalidFile { line: 4 });
+    }
+    if lines.next().is_some() {
+        return Err(HeapError::InvalidFile { line: 5 });
+    }
+    if d == 0 {
+        return Err(HeapError::InvalidD { d });
+    }
+    Ok((d, items))
+}
+
+/// Parses a `name value` line.
+fn field<T: std::str::FromStr>(text: &str, name: &str, line: usize) -> Result<T, HeapError> {
+    text.strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix(' '))
+        .and_then(|value| value.parse().ok())
+        .ok_or(HeapError::InvalidFile { line })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MinOrder;
+    use std::io;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("d-heap-{}-{}.txt", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip");
+        let heap = Heap::new(3, &[5, 2, 9, 1, 7, 3, 8, 8, -4]).unwrap();
+        heap.save_to(&path).unwrap();
+
+        let loaded = Heap::load_from(&path).unwrap();
+        assert!(loaded.is_heap());
+        assert_eq!(loaded.d(), 3);
+        assert_eq!(loaded.as_slice(), heap.as_slice());
+
+        let as_min = Heap::load_with_priority(&path, MinOrder).unwrap();
+        assert!(as_min.is_heap());
+        assert_eq!(as_min.peek_max(), Ok(&-4));
+
+        Heap::new(2, &[]).unwrap().save_to(&path).unwrap();
+        assert_eq!(Heap::load_from(&path).map(|h| h.len()), Ok(0));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_bounded_reports_dropped() {
+        let path = temp_path("bounded");
+        Heap::new(2, &[9, 7, 8, 3]).unwrap().save_to(&path).unwrap();
+        let (heap, dropped) =
+            BoundedHeap::load_bounded(&path, 3, MaxOrder, OverflowPolicy::Reject).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(heap.d(), 2);
+        assert_eq!(heap.as_slice(), &[9, 7, 8]);
+        assert_eq!(dropped, vec![3]);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(
+            Heap::load_from(temp_path("missing")).err(),
+            Some(HeapError::Io {
+                kind: io::ErrorKind::NotFound
+            })
+        );
+
+        assert_eq!(
+            parse("not a heap\n").err(),
+            Some(HeapError::InvalidFile { line: 1 })
+        );
+        assert_eq!(
+            parse("d-heap v1\nd two\n").err(),
+            Some(HeapError::InvalidFile { line: 2 })
+        );
+        assert_eq!(
+            parse("d-heap v1\nd 2\n").err(),
+            Some(HeapError::InvalidFile { line: 3 })
+        );
+        assert_eq!(
+            parse("d-heap v1\nd 2\nsize 3\n1 2\n").err(),
+            Some(HeapError::InvalidFile { line: 4 })
+        );
+        assert_eq!(
+            parse("d-heap v1\nd 0\nsize 0\n\n").err(),
+            Some(HeapError::InvalidD { d: 0 })
+        );
+        assert_eq!(
+            parse("d-heap v1\nd 2\nsize 2\n1 2\n3 4\n").err(),
+            Some(HeapError::InvalidFile { line: 5 })
+        );
+        assert_eq!(
+            parse("d-heap v1\nd 2\nsize 0\n\n\n").err(),
+            Some(HeapError::InvalidFile { line: 5 })
+        );
+        assert_eq!(parse("d-heap v1\nd 4\nsize 0\n"), Ok((4, vec![])));
+    }
+}
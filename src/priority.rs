@@ -0,0 +1,93 @@
+/// Decides which of two values belongs closer to the root of a heap.
+///
+/// The built-in orders are zero-sized, so a `Heap<T, MaxOrder>` compiles to
+/// the same comparisons as a hard-coded max-heap, with no branching on the
+/// order. MaxOrder and MinOrder work for any `T: Ord`.
+pub trait Priority<T = i32> {
+    /// Returns true if `a` must be extracted before `b`.
+    fn higher_than(&self, a: &T, b: &T) -> bool;
+}
+
+/// Orders that compare the values themselves, so any non-decreasing map of
+/// every element (adding a constant, scaling by a positive factor) keeps a
+/// valid heap valid. KeyOrder is excluded since its key may not be monotone.
+pub trait ValueOrder: Priority<i32> {}
+
+/// Larger values first, giving a max-heap.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaxOrder;
+
+/// Smaller values first, giving a min-heap.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinOrder;
+
+/// Flips the wrapped order, like `std::cmp::Reverse` does for `Ord`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reversed<P>(pub P);
+
+/// Orders values by a key function, larger keys first. This is the general
+/// comparator constructor; over i32, MaxOrder is
+/// `KeyOrder::new(|x: &i32| *x as i64)`.
+pub struct KeyOrder<F> {
+    key: F,
+}
+
+impl<F> KeyOrder<F> {
+    pub fn new(key: F) -> Self {
+        Self { key }
+    }
+}
+
+impl<T: Ord> Priority<T> for MaxOrder {
+    fn higher_than(&self, a: &T, b: &T) -> bool {
+        a > b
+    }
+}
+
+impl<T: Ord> Priority<T> for MinOrder {
+    fn higher_than(&self, a: &T, b: &T) -> bool {
+        a < b
+    }
+}
+
+impl<T, P: Priority<T>> Priority<T> for Reversed<P> {
+    fn higher_than(&self, a: &T, b: &T) -> bool {
+        self.0.higher_than(b, a)
+    }
+}
+
+impl ValueOrder for MaxOrder {}
+
+impl ValueOrder for MinOrder {}
+
+impl<P: ValueOrder> ValueOrder for Reversed<P> {}
+
+impl<T, F: Fn(&T) -> i64> Priority<T> for KeyOrder<F> {
+    fn higher_than(&self, a: &T, b: &T) -> bool {
+        (self.key)(a) > (self.key)(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orders() {
+        assert!(MaxOrder.higher_than(&2, &1));
+        assert!(!MaxOrder.higher_than(&1, &1));
+        assert!(MinOrder.higher_than(&1, &2));
+        assert!(Reversed(MaxOrder).higher_than(&1, &2));
+        assert!(Reversed(Reversed(MinOrder)).higher_than(&1, &2));
+
+        let last_digit = KeyOrder::new(|x: &i32| (x % 10) as i64);
+        assert!(last_digit.higher_than(&19, &25));
+        assert!(!last_digit.higher_than(&15, &25));
+
+        assert!(MaxOrder.higher_than(&"b", &"a"));
+        assert!(MinOrder.higher_than(&"a", &"b"));
+        assert!(Reversed(MinOrder).higher_than(&2.5f32.to_bits(), &1.5f32.to_bits()));
+        let by_len = KeyOrder::new(|s: &String| s.len() as i64);
+        assert!(by_len.higher_than(&"pear".to_string(), &"fig".to_string()));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::heap::{Heap, HeapError};
+use crate::priority::Priority;
+use std::time::{Duration, Instant};
+
+/// A token bucket holding up to `capacity` tokens, refilled continuously at
+/// `per_second` tokens per second.
+pub struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(capacity: u32, per_second: u32, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            per_second: per_second as f64,
+            tokens: capacity as f64,
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.per_second).min(self.capacity);
+        self.refilled_at = self.refilled_at.max(now);
+    }
+
+    /// Takes one token, or returns how long until one is available.
+    ///
+    /// # Edge cases
+    /// * A bucket with a rate of 0 or a capacity of 0 never yields another
+    ///   token once empty, and reports Duration::MAX
+    pub fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        if self.per_second <= 0.0 || self.capacity < 1.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - self.tokens) / self.per_second,
+        ))
+    }
+}
+
+/// Outcome of RateLimited::try_extract on a non-empty heap.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Release {
+    Item(i32),
+    /// No token yet; try again after this long.
+    Wait(Duration),
+}
+
+/// A heap whose extractions are paced by a token bucket, for queues that feed
+/// a rate-limited consumer.
+pub struct RateLimited<P: Priority> {
+    heap: Heap<i32, P>,
+    bucket: TokenBucket,
+}
+
+impl<P: Priority> RateLimited<P> {
+    /// Wraps the heap so that every extraction spends a token from `bucket`.
+    pub fn new(heap: Heap<i32, P>, bucket: TokenBucket) -> Self {
+        Self { heap, bucket }
+    }
+
+    /// Returns the heap, e.g. for inserting; only extraction is rate limited.
+    pub fn heap_mut(&mut self) -> &mut Heap<i32, P> {
+        &mut self.heap
+    }
+
+    /// Extracts the root if the bucket has a token at `now`, or returns how
+    /// long to wait for one.
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error without spending a token if heap is empty
+    pub fn try_extract(&mut self, now: Instant) -> Result<Release, HeapError> {
+        if self.heap.is_empty() {
+            return Err(HeapError::EmptyHeap);
+        }
+        match self.bucket.try_take(now) {
+            Ok(()) => self.heap.extract_max().map(Release::Item),
+            Err(wait) => Ok(Release::Wait(wait)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extraction_is_paced() {
+        let start = Instant::now();
+        let bucket = TokenBucket::new(2, 4, start);
+        let mut limited = RateLimited::new(Heap::new(2, &[5, 9, 1, 7]).unwrap(), bucket);
+
+        assert_eq!(limited.try_extract(start), Ok(Release::Item(9)));
+        assert_eq!(limited.try_extract(start), Ok(Release::Item(7)));
+        assert_eq!(
+            limited.try_extract(start),
+            Ok(Release::Wait(Duration::from_millis(250)))
+        );
+
+        let later = start + Duration::from_millis(250);
+        assert_eq!(limited.try_extract(later), Ok(Release::Item(5)));
+        limited.heap_mut().insert(8).unwrap();
+        match limited.try_extract(later + Duration::from_millis(100)) {
+            Ok(Release::Wait(wait)) => assert!(wait > Duration::from_millis(149)),
+            other => panic!("expected to wait, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_empty_heap_keeps_tokens() {
+        let start = Instant::now();
+        let mut limited =
+            RateLimited::new(Heap::new(2, &[]).unwrap(), TokenBucket::new(1, 0, start));
+        assert_eq!(limited.try_extract(start), Err(HeapError::EmptyHeap));
+        limited.heap_mut().insert(3).unwrap();
+        limited.heap_mut().insert(4).unwrap();
+        assert_eq!(limited.try_extract(start), Ok(Release::Item(4)));
+        assert_eq!(limited.try_extract(start), Ok(Release::Wait(Duration::MAX)));
+    }
+}
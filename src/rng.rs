@@ -0,0 +1,70 @@
+/// Small deterministic xorshift64* generator, so runs are reproducible from a seed
+/// without pulling in an external crate.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from the given seed.
+    ///
+    /// # Edge cases
+    /// * A zero seed is remapped, since xorshift never leaves the all-zero state
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Returns the next pseudo-random 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `0..bound`.
+    ///
+    /// # Edge cases
+    /// * Returns 0 when bound is 0
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            0
+        } else {
+            self.next_u64() % bound
+        }
+    }
+
+    /// Returns an i32 in the inclusive range `low..=high`.
+    pub fn range_i32(&mut self, low: i32, high: i32) -> i32 {
+        let span = (high as i64 - low as i64 + 1) as u64;
+        (low as i64 + self.below(span) as i64) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_range_i32_bounds() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let x = rng.range_i32(-5, 5);
+            assert!((-5..=5).contains(&x));
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::heap::{Heap, HeapError};
+use crate::priority::{MaxOrder, MinOrder, Priority};
+
+/// Branching factor of the bounded heap, picked for its unrolled sift path.
+const SELECT_D: u32 = 4;
+
+/// Collects the k items ranked lowest by `priority` into a bounded heap, so the
+/// k-th lowest ends up at the root: a max-heap keeps the k smallest items and a
+/// min-heap the k largest. Each item costs at most one O(log k) heap operation.
+fn k_lowest_heap<I, P>(items: I, k: usize, priority: P) -> Result<Heap<i32, P>, HeapError>
+where
+    I: IntoIterator<Item = i32>,
+    P: Priority + Copy,
+{
+    let mut heap = Heap::with_priority(SELECT_D, &[], priority)?;
+    if k == 0 {
+        return Ok(heap);
+    }
+
+    for item in items {
+        if heap.len() < k {
+            heap.insert(item)?;
+        } else if priority.higher_than(heap.peek_max()?, &item) {
+            heap.replace_max(item)?;
+        }
+    }
+    Ok(heap)
+}
+
+/// Returns the root of the bounded heap if it holds exactly k items.
+fn kth_lowest<I, P>(items: I, k: usize, priority: P) -> Result<Option<i32>, HeapError>
+where
+    I: IntoIterator<Item = i32>,
+    P: Priority + Copy,
+{
+    let heap = k_lowest_heap(items, k, priority)?;
+    if k == 0 || heap.len() < k {
+        Ok(None)
+    } else {
+        heap.peek_max().map(|&root| Some(root))
+    }
+}
+
+/// Drains the bounded heap, returning its items from lowest to highest priority.
+fn n_lowest<I, P>(items: I, n: usize, priority: P) -> Result<Vec<i32>, HeapError>
+where
+    I: IntoIterator<Item = i32>,
+    P: Priority + Copy,
+{
+    let mut heap = k_lowest_heap(items, n, priority)?;
+    let mut items = Vec::with_capacity(heap.len());
+    while let Ok(top) = heap.extract_max() {
+        items.push(top);
+    }
+    items.reverse();
+    Ok(items)
+}
+
+/// Returns the k-th smallest item (1-based) in O(n log k).
+///
+/// # Edge cases
+/// * Returns Ok(None) if k is 0 or there are fewer than k items
+pub fn kth_smallest<I>(items: I, k: usize) -> Result<Option<i32>, HeapError>
+where
+    I: IntoIterator<Item = i32>,
+{
+    kth_lowest(items, k, MaxOrder)
+}
+
+/// Returns the k-th largest item (1-based) in O(n log k).
+///
+/// # Edge cases
+/// * Returns Ok(None) if k is 0 or there are fewer than k items
+pub fn kth_largest<I>(items: I, k: usize) -> Result<Option<i32>, HeapError>
+where
+    I: IntoIterator<Item = i32>,
+{
+    kth_lowest(items, k, MinOrder)
+}
+
+/// Iterator adaptors returning the n largest or smallest items, backed by the
+/// same bounded heap as kth_largest / kth_smallest.
+pub trait SelectExt: Iterator<Item = i32> + Sized {
+    /// Returns the n largest items in descending order, or all of them if there
+    /// are fewer than n.
+    fn n_largest(self, n: usize) -> Result<Vec<i32>, HeapError> {
+        n_lowest(self, n, MinOrder)
+    }
+
+    /// Returns the n smallest items in ascending order, or all of them if there
+    /// are fewer than n.
+    fn n_smallest(self, n: usize) -> Result<Vec<i32>, HeapError> {
+        n_lowest(self, n, MaxOrder)
+    }
+}
+
+impl<I: Iterator<Item = i32>> SelectExt for I {}
+
+/// Handles `select <largest|smallest> <k> <numbers...>`, printing the selected value.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (which, k, numbers) = match args {
+        [which, k, numbers @ ..] => (which, k, numbers),
+        _ => return Err("Usage: select <largest|smallest> <k> <numbers...>".to_string()),
+    };
+
+    let k: usize = k.parse().map_err(|_| format!("Invalid k: {}", k))?;
+    let numbers = numbers
+        .iter()
+        .map(|s| {
+            s.parse::<i32>()
+                .map_err(|_| format!("Invalid number: {}", s))
+        })
+        .collect::<Result<Vec<i32>, String>>()?;
+
+    let selected = match which.as_str() {
+        "largest" => kth_largest(numbers, k),
+        "smallest" => kth_smallest(numbers, k),
+        _ => return Err(format!("Expected largest or smallest, got {}", which)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    match selected {
+        Some(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        None => Err(format!("No {}-th {} item: k is 0 or too large", k, which)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kth_smallest() {
+        let values = [7, 2, 9, 4, 4, 1, 8];
+        assert_eq!(kth_smallest(values, 1).unwrap(), Some(1));
+        assert_eq!(kth_smallest(values, 3).unwrap(), Some(4));
+        assert_eq!(kth_smallest(values, 4).unwrap(), Some(4));
+        assert_eq!(kth_smallest(values, 7).unwrap(), Some(9));
+        assert_eq!(kth_smallest(values, 8).unwrap(), None);
+        assert_eq!(kth_smallest(values, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_kth_largest() {
+        let values = [7, 2, 9, 4, 4, 1, 8, i32::MIN, i32::MAX];
+        assert_eq!(kth_largest(values, 1).unwrap(), Some(i32::MAX));
+        assert_eq!(kth_largest(values, 2).unwrap(), Some(9));
+        assert_eq!(kth_largest(values, 9).unwrap(), Some(i32::MIN));
+        assert_eq!(kth_largest(values, 10).unwrap(), None);
+    }
+
+    #[test]
+    fn test_k_has_no_capacity_limit() {
+        assert_eq!(kth_smallest(0..5000, 1001), Ok(Some(1000)));
+        assert_eq!(kth_largest(0..5000, 4000), Ok(Some(1000)));
+        let largest = (0..5000).n_largest(2000).unwrap();
+        assert_eq!(largest, (3000..5000).rev().collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_n_largest_and_smallest() {
+        let values = [7, 2, 9, 4, 4, 1, 8];
+        assert_eq!(values.iter().copied().n_largest(3).unwrap(), vec![9, 8, 7]);
+        assert_eq!(values.iter().copied().n_smallest(3).unwrap(), vec![1, 2, 4]);
+        assert_eq!(
+            values.iter().copied().n_smallest(0).unwrap(),
+            Vec::<i32>::new()
+        );
+        assert_eq!((0..3).n_largest(10).unwrap(), vec![2, 1, 0]);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::heap::{Heap, HeapError, HEAP_MAX_SIZE};
+use crate::priority::Priority;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// A heap split into independently locked shards, so concurrent pushes and
+/// pops mostly touch different locks.
+///
+/// `push` spreads items round-robin. `pop_max` peeks every shard root and
+/// extracts from the best one. Another thread may change that shard between
+/// the peek and the extract, so results are only approximately in order.
+pub struct ShardedHeap<P: Priority + Clone> {
+    shards: Vec<Mutex<Heap<i32, P>>>,
+    priority: P,
+    next_shard: AtomicUsize,
+}
+
+impl<P: Priority + Clone> ShardedHeap<P> {
+    /// Creates `shards` empty d-ary heaps of HEAP_MAX_SIZE items each, ordered
+    /// by `priority`.
+    ///
+    /// # Edge cases
+    /// * Returns InvalidD error if d is 0
+    /// * A shard count of 0 is treated as 1
+    pub fn new(shards: usize, d: u32, priority: P) -> Result<Self, HeapError> {
+        let shards = (0..shards.max(1))
+            .map(|_| Heap::bounded(d, HEAP_MAX_SIZE, &[], priority.clone()).map(Mutex::new))
+            .collect::<Result<Vec<_>, HeapError>>()?;
+        Ok(Self {
+            shards,
+            priority,
+            next_shard: AtomicUsize::new(0),
+        })
+    }
+
+    fn lock(&self, shard: usize) -> MutexGuard<'_, Heap<i32, P>> {
+        self.shards[shard].lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Inserts the item into the next shard in round-robin order.
+    ///
+    /// # Edge cases
+    /// * Falls through to the following shards if the chosen one is full
+    /// * Returns HeapFull error with the total capacity if every shard is full
+    pub fn push(&self, item: i32) -> Result<(), HeapError> {
+        let start = self.next_shard.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.shards.len() {
+            let shard = (start + offset) % self.shards.len();
+            match self.lock(shard).insert(item) {
+                Err(HeapError::HeapFull { .. }) => continue,
+                result => return result.map(|_| ()),
+            }
+        }
+
+        let capacity = self.shards.len() * HEAP_MAX_SIZE;
+        Err(HeapError::HeapFull { capacity })
+    }
+
+    /// Removes and returns the best item among the shard roots.
+    ///
+    /// # Edge cases
+    /// * Returns None only if every shard was empty when scanned
+    pub fn pop_max(&self) -> Option<i32> {
+        loop {
+            let mut best: Option<(usize, i32)> = None;
+            for shard in 0..self.shards.len() {
+                if let Ok(&root) = self.lock(shard).peek_max() {
+                    match best {
+                        Some((_, best_root)) if !self.priority.higher_than(&root, &best_root) => {}
+                        _ => best = Some((shard, root)),
+                    }
+                }
+            }
+
+            let (shard, _) = best?;
+            // The shard may have been drained since the scan; if so, look again
+            if let Ok(item) = self.lock(shard).extract_max() {
+                return Some(item);
+            }
+        }
+    }
+
+    /// Returns the total number of items across all shards.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len())
+            .map(|shard| self.lock(shard).len())
+            .sum()
+    }
+
+    /// Returns true if every shard is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MaxOrder;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_single_threaded_order() {
+        let heap = ShardedHeap::new(3, 2, MaxOrder).unwrap();
+        for x in [5, 1, 9, 3, 7, 2, 8] {
+            assert!(heap.push(x).is_ok());
+        }
+        assert_eq!(heap.len(), 7);
+
+        // Without concurrent writers, comparing roots gives exact order
+        let mut out = Vec::new();
+        while let Some(x) = heap.pop_max() {
+            out.push(x);
+        }
+        assert_eq!(out, vec![9, 8, 7, 5, 3, 2, 1]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_push_pop() {
+        let heap = Arc::new(ShardedHeap::new(4, 4, MaxOrder).unwrap());
+        let producers: Vec<_> = (0..4)
+            .map(|t| {
+                let heap = Arc::clone(&heap);
+                thread::spawn(move || {
+                    for i in 0..200 {
+                        heap.push(t * 200 + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for p in producers {
+            p.join().unwrap();
+        }
+
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let heap = Arc::clone(&heap);
+                thread::spawn(move || {
+                    let mut popped = Vec::new();
+                    while let Some(x) = heap.pop_max() {
+                        popped.push(x);
+                    }
+                    popped
+                })
+            })
+            .collect();
+
+        let mut all: Vec<i32> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort_unstable();
+        assert_eq!(all, (0..800).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_push_when_full() {
+        let heap = ShardedHeap::new(2, 2, MaxOrder).unwrap();
+        let capacity = 2 * crate::heap::HEAP_MAX_SIZE;
+        for i in 0..capacity {
+            assert!(heap.push(i as i32).is_ok());
+        }
+        assert_eq!(heap.push(0), Err(HeapError::HeapFull { capacity }));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::heap::{Heap, HeapError};
+use crate::priority::Priority;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Value of the cached head while the heap is empty; any real item fits in
+/// the low 32 bits.
+const EMPTY_HEAD: u64 = 1 << 32;
+
+fn encode_head(head: Option<i32>) -> u64 {
+    match head {
+        Some(item) => item as u32 as u64,
+        None => EMPTY_HEAD,
+    }
+}
+
+fn decode_head(bits: u64) -> Option<i32> {
+    if bits == EMPTY_HEAD {
+        None
+    } else {
+        Some(bits as u32 as i32)
+    }
+}
+
+/// A heap shared behind one lock, with the current root mirrored into an
+/// atomic so `peek` is a single load and never waits for the lock.
+///
+/// Every mutation republishes the root before releasing the lock, so `peek`
+/// always returns a root the heap actually had, possibly one that a
+/// concurrent update is about to replace.
+pub struct SharedHeap<P: Priority> {
+    heap: Mutex<Heap<i32, P>>,
+    head: AtomicU64,
+}
+
+impl<P: Priority> SharedHeap<P> {
+    /// Wraps the heap and publishes its current root.
+    pub fn new(heap: Heap<i32, P>) -> Self {
+        let head = AtomicU64::new(encode_head(heap.peek_max().ok().copied()));
+        Self {
+            heap: Mutex::new(heap),
+            head,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Heap<i32, P>> {
+        self.heap.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn publish_head(&self, heap: &Heap<i32, P>) {
+        self.head.store(
+            encode_head(heap.peek_max().ok().copied()),
+            Ordering::Release,
+        );
+    }
+
+    /// Returns the root without locking, or None if the heap is empty.
+    pub fn peek(&self) -> Option<i32> {
+        decode_head(self.head.load(Ordering::Acquire))
+    }
+
+    /// Inserts the item.
+    ///
+    /// # Edge cases
+    /// * Returns HeapFull error if the heap is already full
+    pub fn push(&self, item: i32) -> Result<(), HeapError> {
+        self.with_heap(|heap| heap.insert(item).map(|_| ()))
+    }
+
+    /// Removes and returns the root, or None if the heap is empty.
+    pub fn pop(&self) -> Option<i32> {
+        self.with_heap(|heap| heap.extract_max().ok())
+    }
+
+    /// Runs `f` with the heap locked and republishes the root afterwards.
+    pub fn with_heap<R>(&self, f: impl FnOnce(&mut Heap<i32, P>) -> R) -> R {
+        let mut heap = self.lock();
+        let result = f(&mut heap);
+        self.publish_head(&heap);
+        result
+    }
+
+    /// Returns the number of items, taking the lock.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if the heap holds no items, without taking the lock.
+    pub fn is_empty(&self) -> bool {
+        self.peek().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MinOrder;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_peek_tracks_root() {
+        let shared = SharedHeap::new(Heap::new(3, &[4, -7, 2]).unwrap());
+        assert_eq!(shared.peek(), Some(4));
+        shared.push(9).unwrap();
+        assert_eq!(shared.peek(), Some(9));
+        assert_eq!(shared.pop(), Some(9));
+        shared.with_heap(|h| h.replace_max(-20)).unwrap();
+        assert_eq!(shared.peek(), Some(2));
+        assert_eq!(shared.pop(), Some(2));
+        assert_eq!(shared.pop(), Some(-7));
+        assert_eq!(shared.peek(), Some(-20));
+        assert_eq!(shared.pop(), Some(-20));
+        assert_eq!(shared.peek(), None);
+        assert_eq!(shared.len(), 0);
+    }
+
+    #[test]
+    fn test_peek_handles_extreme_values() {
+        let shared = SharedHeap::new(Heap::with_priority(2, &[], MinOrder).unwrap());
+        shared.push(i32::MAX).unwrap();
+        assert_eq!(shared.peek(), Some(i32::MAX));
+        shared.push(i32::MIN).unwrap();
+        assert_eq!(shared.peek(), Some(i32::MIN));
+        shared.push(-1).unwrap();
+        assert_eq!(shared.peek(), Some(i32::MIN));
+    }
+
+    #[test]
+    fn test_peek_while_pushing() {
+        let shared = Arc::new(SharedHeap::new(Heap::new(4, &[]).unwrap()));
+        let poller = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                let mut last = None;
+                while last != Some(499) {
+                    let head = shared.peek();
+                    // Only ascending values are pushed, so the head never goes down
+                    assert!(head >= last);
+                    last = head;
+                }
+            })
+        };
+        for i in 0..500 {
+            shared.push(i).unwrap();
+        }
+        poller.join().unwrap();
+    }
+}
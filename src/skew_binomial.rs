@@ -0,0 +1,380 @@
+use crate::heap::HeapError;
+use crate::priority::{MaxOrder, Priority};
+use std::collections::VecDeque;
+use std::mem;
+
+/// A skew binomial tree. Besides its binomial children, each node keeps up
+/// to `rank` extra elements that a skew link placed under it.
+struct Tree<T> {
+    rank: u32,
+    root: T,
+    extras: Vec<T>,
+    /// Ordered by increasing rank.
+    children: Vec<Tree<T>>,
+}
+
+/// A primitive skew binomial queue of `T` ordered by `P`, following Brodal
+/// and Okasaki. insert does at most one skew link, so it is O(1) in the
+/// worst case; meld, peek_max and extract_max walk the O(log n) trees.
+struct SkewQueue<T, P> {
+    /// Ordered by increasing rank; only the first two trees may share a rank.
+    trees: VecDeque<Tree<T>>,
+    priority: P,
+}
+
+impl<T, P: Priority<T>> SkewQueue<T, P> {
+    fn new(priority: P) -> Self {
+        Self {
+            trees: VecDeque::new(),
+            priority,
+        }
+    }
+
+    /// Inserts an item with at most one skew link, in O(1) worst case.
+    fn insert(&mut self, item: T) {
+        let same_rank = matches!(
+            (self.trees.front(), self.trees.get(1)),
+            (Some(a), Some(b)) if a.rank == b.rank
+        );
+
+        let tree = if same_rank {
+            let (Some(first), Some(second)) = (self.trees.pop_front(), self.trees.pop_front())
+            else {
+                unreachable!("both trees were just checked");
+            };
+            self.skew_link(item, first, second)
+        } else {
+            Tree {
+                rank: 0,
+                root: item,
+                extras: Vec::new(),
+                children: Vec::new(),
+            }
+        };
+        self.trees.push_front(tree);
+    }
+
+    /// Moves every element of `other` into this queue in O(log n).
+    fn meld(&mut self, mut other: Self) {
+        let mine = mem::take(&mut self.trees);
+        let mine = self.normalized(mine);
+        let theirs = self.normalized(mem::take(&mut other.trees));
+        self.trees = self.merge_trees(mine, theirs);
+    }
+
+    /// Removes and returns the highest-priority element in O(log n).
+    fn extract_max(&mut self) -> Option<T> {
+        let tree = self.best_tree().and_then(|idx| self.trees.remove(idx))?;
+
+        let rest = mem::take(&mut self.trees);
+        let rest = self.normalized(rest);
+        let children = tree.children.into_iter().collect();
+        self.trees = self.merge_trees(rest, children);
+
+        for extra in tree.extras {
+            self.insert(extra);
+        }
+        Some(tree.root)
+    }
+
+    fn best_tree(&self) -> Option<usize> {
+        (0..self.trees.len()).reduce(|best, idx| {
+            if self
+                .priority
+                .higher_than(&self.trees[idx].root, &self.trees[best].root)
+            {
+                idx
+            } else {
+                best
+            }
+        })
+    }
+
+    /// Makes the tree with the lower-priority root a child of the other one.
+    fn link(&self, mut a: Tree<T>, mut b: Tree<T>) -> Tree<T> {
+        if self.priority.higher_than(&b.root, &a.root) {
+            mem::swap(&mut a, &mut b);
+        }
+        a.rank += 1;
+        a.children.push(b);
+        a
+    }
+
+    /// Links two trees of equal rank and places `item` either at the new root
+    /// or among its extras.
+    fn skew_link(&self, item: T, a: Tree<T>, b: Tree<T>) -> Tree<T> {
+        let mut tree = self.link(a, b);
+        if self.priority.higher_than(&item, &tree.root) {
+            let root = mem::replace(&mut tree.root, item);
+            tree.extras.push(root);
+        } else {
+            tree.extras.push(item);
+        }
+        tree
+    }
+
+    /// Inserts a tree whose rank is at most that of the first tree, linking
+    /// while ranks collide.
+    fn insert_tree(&self, mut tree: Tree<T>, mut trees: VecDeque<Tree<T>>) -> VecDeque<Tree<T>> {
+        while let Some(front) = trees.pop_front() {
+            if tree.rank < front.rank {
+                trees.push_front(front);
+                break;
+            }
+            tree = self.link(tree, front);
+        }
+        trees.push_front(tree);
+        trees
+    }
+
+    /// Removes the one rank collision insert may leave at the front.
+    fn normalized(&self, mut trees: VecDeque<Tree<T>>) -> VecDeque<Tree<T>> {
+        match trees.pop_front() {
+            Some(first) => self.insert_tree(first, trees),
+            None => trees,
+        }
+    }
+
+    /// Merges two tree lists with distinct ranks, like adding binary numbers.
+    fn merge_trees(&self, mut a: VecDeque<Tree<T>>, mut b: VecDeque<Tree<T>>) -> VecDeque<Tree<T>> {
+        let (first_a, first_b) = match (a.pop_front(), b.pop_front()) {
+            (Some(x), Some(y)) => (x, y),
+            (Some(x), None) => {
+                a.push_front(x);
+                return a;
+            }
+            (None, Some(y)) => {
+                b.push_front(y);
+                return b;
+            }
+            (None, None) => return a,
+        };
+
+        if first_a.rank == first_b.rank {
+            let linked = self.link(first_a, first_b);
+            let merged = self.merge_trees(a, b);
+            return self.insert_tree(linked, merged);
+        }
+
+        let lower = if first_a.rank < first_b.rank {
+            b.push_front(first_b);
+            first_a
+        } else {
+            a.push_front(first_a);
+            first_b
+        };
+        let mut merged = self.merge_trees(a, b);
+        merged.push_front(lower);
+        merged
+    }
+}
+
+impl<T, P> SkewQueue<T, P> {
+    /// Moves every element into `out` without recursing into the trees.
+    fn drain_into(mut self, out: &mut Vec<T>) {
+        let mut trees: Vec<Tree<T>> = self.trees.drain(..).collect();
+        while let Some(tree) = trees.pop() {
+            out.push(tree.root);
+            out.extend(tree.extras);
+            trees.extend(tree.children);
+        }
+    }
+}
+
+/// A non-empty bootstrapped heap: its best element, and a primitive queue
+/// of the other non-empty heaps melded into it, ranked by their roots.
+struct Rooted<P> {
+    root: i32,
+    rest: SkewQueue<Rooted<P>, ByRoot<P>>,
+}
+
+/// Ranks bootstrapped heaps by their roots under `P`.
+#[derive(Clone)]
+struct ByRoot<P>(P);
+
+impl<P: Priority> Priority<Rooted<P>> for ByRoot<P> {
+    fn higher_than(&self, a: &Rooted<P>, b: &Rooted<P>) -> bool {
+        self.0.higher_than(&a.root, &b.root)
+    }
+}
+
+/// A worst-case efficient heap ordered by `P`: Brodal and Okasaki's skew
+/// binomial queue, bootstrapped so the best element sits at the top.
+///
+/// The heap is its best element plus a primitive skew binomial queue of
+/// other such heaps ranked by their roots. Melding two heaps inserts the one
+/// with the lower root into the other's queue, a single O(1) skew insert, and
+/// insert is a meld with a one-element heap. So insert, meld and peek_max
+/// are O(1) in the worst case, not amortized, while extract_max pulls the
+/// best heap out of the queue and melds the two queues in O(log n). There
+/// is no capacity limit.
+pub struct SkewBinomialHeap<P: Priority + Clone = MaxOrder> {
+    top: Option<Rooted<P>>,
+    len: usize,
+    priority: P,
+}
+
+impl<P: Priority + Clone> SkewBinomialHeap<P> {
+    /// Creates an empty heap ordered by `priority`.
+    pub fn new(priority: P) -> Self {
+        Self {
+            top: None,
+            len: 0,
+            priority,
+        }
+    }
+
+    /// Inserts an item in O(1) worst case, as a meld with a one-element heap.
+    pub fn insert(&mut self, item: i32) {
+        let single = Rooted {
+            root: item,
+            rest: SkewQueue::new(ByRoot(self.priority.clone())),
+        };
+        self.top = Some(match self.top.take() {
+            Some(top) => self.join(top, single),
+            None => single,
+        });
+        self.len += 1;
+    }
+
+    /// Moves every element of `other` into this heap in O(1) worst case.
+    pub fn meld(&mut self, mut other: Self) {
+        self.top = match (self.top.take(), other.top.take()) {
+            (Some(mine), Some(theirs)) => Some(self.join(mine, theirs)),
+            (mine, theirs) => mine.or(theirs),
+        };
+        self.len += mem::take(&mut other.len);
+    }
+
+    /// Returns the highest-priority element without removing it, in O(1).
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty
+    pub fn peek_max(&self) -> Result<i32, HeapError> {
+        self.top
+            .as_ref()
+            .map(|top| top.root)
+            .ok_or(HeapError::EmptyHeap)
+    }
+
+    /// Removes and returns the highest-priority element in O(log n).
+    ///
+    /// # Edge cases
+    /// * Returns EmptyHeap error if heap is empty
+    pub fn extract_max(&mut self) -> Result<i32, HeapError> {
+        let Rooted { root, mut rest } = self.top.take().ok_or(HeapError::EmptyHeap)?;
+        self.top = rest.extract_max().map(|mut next| {
+            next.rest.meld(rest);
+            next
+        });
+        self.len -= 1;
+        Ok(root)
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the heap holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Melds two non-empty heaps: the one with the lower root goes into the
+    /// other's queue.
+    fn join(&self, mut a: Rooted<P>, mut b: Rooted<P>) -> Rooted<P> {
+        if self.priority.higher_than(&b.root, &a.root) {
+            mem::swap(&mut a, &mut b);
+        }
+        a.rest.insert(b);
+        a
+    }
+}
+
+impl<P: Priority + Clone> Drop for SkewBinomialHeap<P> {
+    /// Takes the nested heaps apart one level at a time: a long run of
+    /// rising inserts nests them n deep, too deep to drop recursively.
+    fn drop(&mut self) {
+        let mut pending: Vec<Rooted<P>> = self.top.take().into_iter().collect();
+        while let Some(rooted) = pending.pop() {
+            rooted.rest.drain_into(&mut pending);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::MinOrder;
+    use crate::rng::Rng;
+
+    fn drain<P: Priority + Clone>(heap: &mut SkewBinomialHeap<P>) -> Vec<i32> {
+        std::iter::from_fn(|| heap.extract_max().ok()).collect()
+    }
+
+    #[test]
+    fn test_extracts_in_order() {
+        let mut rng = Rng::new(7);
+        let mut heap = SkewBinomialHeap::new(MaxOrder);
+        let mut values: Vec<i32> = (0..3000).map(|_| rng.range_i32(-500, 500)).collect();
+        for &x in &values {
+            heap.insert(x);
+        }
+        assert_eq!(heap.len(), values.len());
+        assert_eq!(
+            heap.peek_max(),
+            values.iter().max().copied().ok_or(HeapError::EmptyHeap)
+        );
+
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(drain(&mut heap), values);
+        assert!(heap.is_empty());
+        assert_eq!(heap.extract_max(), Err(HeapError::EmptyHeap));
+    }
+
+    #[test]
+    fn test_meld_and_interleaved_ops() {
+        let mut a = SkewBinomialHeap::new(MinOrder);
+        let mut b = SkewBinomialHeap::new(MinOrder);
+        for x in 0..100 {
+            a.insert(x * 2);
+            b.insert(x * 2 + 1);
+        }
+        assert_eq!(a.extract_max(), Ok(0));
+        a.meld(b);
+        assert_eq!(a.len(), 199);
+        a.insert(-1);
+        assert_eq!(a.extract_max(), Ok(-1));
+        assert_eq!(drain(&mut a), (1..200).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_many_melds_and_deep_nesting() {
+        let mut rng = Rng::new(19);
+        let mut heap = SkewBinomialHeap::new(MaxOrder);
+        let mut values = Vec::new();
+        for _ in 0..300 {
+            let mut part = SkewBinomialHeap::new(MaxOrder);
+            for _ in 0..rng.below(10) {
+                let x = rng.range_i32(-1000, 1000);
+                part.insert(x);
+                values.push(x);
+            }
+            heap.meld(part);
+            assert_eq!(heap.peek_max().ok(), values.iter().max().copied());
+        }
+        assert_eq!(heap.len(), values.len());
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(drain(&mut heap), values);
+
+        // Every rising insert nests the previous top one level deeper
+        let mut rising = SkewBinomialHeap::new(MaxOrder);
+        for x in 0..200_000 {
+            rising.insert(x);
+        }
+        assert_eq!(rising.extract_max(), Ok(199_999));
+        assert_eq!(rising.peek_max(), Ok(199_998));
+        drop(rising);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::heap::Heap;
+use crate::priority::Priority;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A heap as published under one epoch.
+struct Published<P: Priority> {
+    epoch: u64,
+    heap: Heap<i32, P>,
+}
+
+/// A consistent, immutable view of the heap as of one published epoch.
+pub struct Snapshot<P: Priority> {
+    published: Arc<Published<P>>,
+}
+
+impl<P: Priority> Snapshot<P> {
+    /// Returns the number of updates published before this snapshot was taken.
+    pub fn epoch(&self) -> u64 {
+        self.published.epoch
+    }
+}
+
+impl<P: Priority> Clone for Snapshot<P> {
+    fn clone(&self) -> Self {
+        Self {
+            published: Arc::clone(&self.published),
+        }
+    }
+}
+
+impl<P: Priority> Deref for Snapshot<P> {
+    type Target = Heap<i32, P>;
+
+    fn deref(&self) -> &Heap<i32, P> {
+        &self.published.heap
+    }
+}
+
+/// A read-mostly shared heap in the RCU style: the writer updates a private
+/// copy and publishes it as a new epoch, while readers keep whichever
+/// snapshot they took for as long as they like.
+///
+/// Taking a snapshot never locks or waits: the reader bumps a reader count,
+/// loads the current pointer and takes a reference on it. An update swaps
+/// in the new epoch and keeps the reference the pointer held on the old one
+/// in a retired list, dropped once an update sees the reader count at zero,
+/// so no reader is ever caught between loading a pointer and referencing
+/// it. Writers are serialized and each update copies the whole heap, which
+/// suits rare writes and frequent reads.
+pub struct SnapshotHeap<P: Priority + Clone> {
+    /// Holds one strong count, made by Arc::into_raw, on the current epoch.
+    current: AtomicPtr<Published<P>>,
+    /// Readers between loading `current` and taking their own reference.
+    readers: AtomicUsize,
+    /// Replaced epochs a reader may still be about to reference; the lock
+    /// also serializes writers.
+    retired: Mutex<Vec<Arc<Published<P>>>>,
+}
+
+impl<P: Priority + Clone> SnapshotHeap<P> {
+    /// Publishes the given heap as epoch 0.
+    pub fn new(heap: Heap<i32, P>) -> Self {
+        let published = Arc::new(Published { epoch: 0, heap });
+        Self {
+            current: AtomicPtr::new(Arc::into_raw(published).cast_mut()),
+            readers: AtomicUsize::new(0),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the most recently published snapshot, without locking.
+    pub fn snapshot(&self) -> Snapshot<P> {
+        // SeqCst throughout, so the count, the load and an update's swap
+        // and check fall in one order every thread agrees on
+        self.readers.fetch_add(1, Ordering::SeqCst);
+        let current = self.current.load(Ordering::SeqCst);
+        // SAFETY: `current` came from Arc::into_raw and its strong count is
+        // only given up by an update that, after swapping it out, saw no
+        // reader inside this window; we are counted, so it is still alive.
+        let published = unsafe {
+            Arc::increment_strong_count(current);
+            Arc::from_raw(current)
+        };
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+        Snapshot { published }
+    }
+
+    /// Applies `f` to a private copy of the latest heap and publishes the
+    /// result as the next epoch, returning whatever `f` returned.
+    ///
+    /// # Edge cases
+    /// * The copy is published even if `f` reports an error, so `f` should
+    ///   leave the heap unchanged on failure, as the heap methods do
+    /// * A replaced epoch is freed by a later update, or when the
+    ///   SnapshotHeap is dropped, if a reader was mid-snapshot at the time
+    pub fn update<T>(&self, f: impl FnOnce(&mut Heap<i32, P>) -> T) -> T {
+        let mut retired = self.retired.lock().unwrap_or_else(|e| e.into_inner());
+        let base = self.snapshot();
+        let mut heap = Heap::clone(&base);
+        let result = f(&mut heap);
+
+        let next = Arc::new(Published {
+            epoch: base.epoch() + 1,
+            heap,
+        });
+        let old = self
+            .current
+            .swap(Arc::into_raw(next).cast_mut(), Ordering::SeqCst);
+        // SAFETY: the swap handed us the strong count `current` held on old
+        retired.push(unsafe { Arc::from_raw(old) });
+        // Any reader that loaded a retired pointer did so before its swap,
+        // so once none are counted, all of them hold their own reference
+        if self.readers.load(Ordering::SeqCst) == 0 {
+            retired.clear();
+        }
+        result
+    }
+}
+
+impl<P: Priority + Clone> Drop for SnapshotHeap<P> {
+    fn drop(&mut self) {
+        let current = *self.current.get_mut();
+        // SAFETY: `current` holds a strong count from Arc::into_raw, and
+        // with &mut self no reader can be loading it
+        drop(unsafe { Arc::from_raw(current) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_snapshot_unaffected_by_later_updates() {
+        let shared = SnapshotHeap::new(Heap::new(2, &[5, 3, 8]).unwrap());
+        let before = shared.snapshot();
+
+        shared.update(|h| h.insert(10)).unwrap();
+        assert_eq!(shared.update(|h| h.extract_max()), Ok(10));
+        assert_eq!(shared.update(|h| h.extract_max()), Ok(8));
+
+        assert_eq!(before.epoch(), 0);
+        assert_eq!(
+            before.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![8, 5, 3]
+        );
+
+        // With no reader in flight, every replaced epoch was released
+        assert_eq!(Arc::strong_count(&before.published), 1);
+        assert!(shared.retired.lock().unwrap().is_empty());
+
+        let after = shared.snapshot();
+        assert_eq!(after.epoch(), 3);
+        assert_eq!(
+            after.sorted_iter().copied().collect::<Vec<i32>>(),
+            vec![5, 3]
+        );
+    }
+
+    #[test]
+    fn test_readers_see_consistent_heaps() {
+        let shared = Arc::new(SnapshotHeap::new(Heap::new(4, &[]).unwrap()));
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    let mut last_epoch = 0;
+                    for _ in 0..200 {
+                        let snap = shared.snapshot();
+                        assert!(snap.epoch() >= last_epoch);
+                        assert!(snap.is_heap());
+                        assert_eq!(snap.len() as u64, snap.epoch());
+                        last_epoch = snap.epoch();
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..200 {
+            shared.update(|h| h.insert(i)).unwrap();
+        }
+        for r in readers {
+            r.join().unwrap();
+        }
+        assert_eq!(shared.snapshot().peek_max(), Ok(&199));
+    }
+}
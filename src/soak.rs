@@ -0,0 +1,159 @@
+use crate::model::Op;
+use crate::rng::Rng;
+use crate::stress::{self, TrackedHeap};
+use std::fs;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Operations applied to each heap between invariant checks.
+const BATCH_OPS: u64 = 1000;
+
+/// Options for a soak run, parsed from the command line.
+struct SoakOptions {
+    hours: f64,
+    heaps: usize,
+    seed: u64,
+    report_every: Duration,
+}
+
+/// Parses `--hours H`, `--heaps N`, `--seed N` and `--report-secs N`.
+///
+/// # Edge cases
+/// * Without `--seed`, a seed is derived from the current time and printed
+/// * `--heaps 0` and non-positive `--hours` are rejected
+fn parse_options(args: &[String]) -> Result<SoakOptions, String> {
+    let mut options = SoakOptions {
+        hours: 1.0,
+        heaps: 1,
+        seed: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_nanos() as u64)
+            .unwrap_or(0),
+        report_every: Duration::from_secs(60),
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag.as_str() {
+            "--hours" => match value.parse::<f64>() {
+                Ok(hours) if hours > 0.0 && hours.is_finite() => options.hours = hours,
+                _ => return Err(invalid()),
+            },
+            "--heaps" => match value.parse::<usize>() {
+                Ok(heaps) if heaps > 0 => options.heaps = heaps,
+                _ => return Err(invalid()),
+            },
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--report-secs" => {
+                options.report_every = Duration::from_secs(value.parse().map_err(|_| invalid())?)
+            }
+            _ => return Err(format!("Unknown option: {}", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Returns the resident set size of this process in kB, where the platform exposes it.
+fn resident_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// Formats a memory reading and its growth since the start of the run.
+fn describe_memory(start_kb: Option<u64>) -> String {
+    match (start_kb, resident_kb()) {
+        (Some(start), Some(now)) => {
+            format!("rss={}kB growth={:+}kB", now, now as i64 - start as i64)
+        }
+        _ => "rss=n/a".to_string(),
+    }
+}
+
+/// Runs random operations on `--heaps` heaps for `--hours`, checking every heap's
+/// invariant after each batch and periodically reporting throughput and memory.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let mut rng = Rng::new(options.seed);
+    let mut heaps = (0..options.heaps)
+        .map(|_| TrackedHeap::new())
+        .collect::<Result<Vec<TrackedHeap>, String>>()?;
+
+    let duration = Duration::from_secs_f64(options.hours * 3600.0);
+    let start = Instant::now();
+    let start_kb = resident_kb();
+    let mut last_report = start;
+    let mut total_ops: u64 = 0;
+
+    println!(
+        "Soak: seed={} hours={} heaps={} {}",
+        options.seed,
+        options.hours,
+        options.heaps,
+        describe_memory(start_kb)
+    );
+
+    while start.elapsed() < duration {
+        for (heap_idx, tracked) in heaps.iter_mut().enumerate() {
+            for _ in 0..BATCH_OPS {
+                let op = stress::random_op(&mut rng, tracked.heap());
+                total_ops += 1;
+                if let Err(reason) = tracked.apply(&op) {
+                    report_failure(&options, heap_idx, total_ops, Some(&op), &reason, tracked);
+                    return Err(format!("soak run failed after {} operations", total_ops));
+                }
+            }
+            if let Err(reason) = tracked.check_invariant() {
+                report_failure(&options, heap_idx, total_ops, None, &reason, tracked);
+                return Err(format!("soak run failed after {} operations", total_ops));
+            }
+        }
+
+        if last_report.elapsed() >= options.report_every {
+            last_report = Instant::now();
+            println!(
+                "[{:>8.1}s] ops={} {}",
+                start.elapsed().as_secs_f64(),
+                total_ops,
+                describe_memory(start_kb)
+            );
+        }
+    }
+
+    println!(
+        "Soak run passed: {} operations in {:.1}s, {}",
+        total_ops,
+        start.elapsed().as_secs_f64(),
+        describe_memory(start_kb)
+    );
+    Ok(())
+}
+
+/// Prints everything needed to investigate a failed run.
+fn report_failure(
+    options: &SoakOptions,
+    heap_idx: usize,
+    total_ops: u64,
+    op: Option<&Op>,
+    reason: &str,
+    tracked: &TrackedHeap,
+) {
+    eprintln!("Soak run failed!");
+    eprintln!("  seed:   {}", options.seed);
+    eprintln!("  heap:   {} of {}", heap_idx, options.heaps);
+    eprintln!("  ops:    {}", total_ops);
+    if let Some(op) = op {
+        eprintln!("  op:     {:?}", op);
+    }
+    eprintln!("  reason: {}", reason);
+    tracked.heap().print();
+}
@@ -0,0 +1,159 @@
+use crate::heap::{Heap, HeapError};
+use crate::priority::Priority;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// State shared between a HeapStream and its senders.
+struct StreamState<P: Priority> {
+    heap: Heap<i32, P>,
+    senders: usize,
+    waker: Option<Waker>,
+}
+
+/// A `Stream` over a heap that yields the best available item, waiting while
+/// the heap is empty for a StreamSender to push more.
+///
+/// The stream ends once the heap is empty and no senders are left.
+pub struct HeapStream<P: Priority> {
+    state: Arc<Mutex<StreamState<P>>>,
+}
+
+/// Cloneable handle for pushing items into a HeapStream.
+pub struct StreamSender<P: Priority> {
+    state: Arc<Mutex<StreamState<P>>>,
+}
+
+fn lock<P: Priority>(state: &Mutex<StreamState<P>>) -> MutexGuard<'_, StreamState<P>> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<P: Priority> Heap<i32, P> {
+    /// Turns the heap into a stream yielding its items in priority order.
+    pub fn into_stream(self) -> HeapStream<P> {
+        HeapStream {
+            state: Arc::new(Mutex::new(StreamState {
+                heap: self,
+                senders: 0,
+                waker: None,
+            })),
+        }
+    }
+}
+
+impl<P: Priority> HeapStream<P> {
+    /// Returns a new sender; the stream keeps waiting on an empty heap while
+    /// any sender is alive.
+    pub fn sender(&self) -> StreamSender<P> {
+        lock(&self.state).senders += 1;
+        StreamSender {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<P: Priority> Stream for HeapStream<P> {
+    type Item = i32;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<i32>> {
+        let mut state = lock(&self.state);
+        match state.heap.extract_max() {
+            Ok(item) => Poll::Ready(Some(item)),
+            Err(_) if state.senders == 0 => Poll::Ready(None),
+            Err(_) => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<P: Priority> StreamSender<P> {
+    /// Inserts the item and wakes the stream if it is waiting.
+    ///
+    /// # Edge cases
+    /// * Returns HeapFull error if the heap is already full
+    pub fn push(&self, item: i32) -> Result<(), HeapError> {
+        let mut state = lock(&self.state);
+        state.heap.insert(item)?;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+impl<P: Priority> Clone for StreamSender<P> {
+    fn clone(&self) -> Self {
+        lock(&self.state).senders += 1;
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<P: Priority> Drop for StreamSender<P> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.state);
+        state.senders -= 1;
+        // The last sender going away may end a waiting stream
+        if state.senders == 0 {
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Polls the stream to its end on the current thread, parking while pending.
+    fn collect<P: Priority>(mut stream: HeapStream<P>) -> Vec<i32> {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut items = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(item)) => items.push(item),
+                Poll::Ready(None) => return items,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_drains_in_order() {
+        let stream = Heap::new(3, &[4, 9, 1, 7]).unwrap().into_stream();
+        assert_eq!(collect(stream), vec![9, 7, 4, 1]);
+    }
+
+    #[test]
+    fn test_stream_waits_for_senders() {
+        let stream = Heap::new(2, &[]).unwrap().into_stream();
+        let sender = stream.sender();
+        let producer = thread::spawn(move || {
+            for i in 0..100 {
+                sender.push(i).unwrap();
+            }
+        });
+
+        let items = collect(stream);
+        producer.join().unwrap();
+        let mut sorted = items.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..100).collect::<Vec<i32>>());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::heap::{Heap, HEAP_MAX_SIZE};
+use crate::model::{self, Model, Op};
+use crate::priority::MaxOrder;
+use crate::rng::Rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_OPS: u64 = 1_000_000;
+const DEFAULT_CHECK_EVERY: u64 = 1000;
+
+/// Options for a stress run, parsed from the command line.
+struct StressOptions {
+    seed: u64,
+    ops: u64,
+    check_every: u64,
+}
+
+/// Picks a random operation, with inserts slightly ahead of extractions and
+/// deletes so the heap drifts between empty and full over a long run. Key
+/// changes and deletes target a random live handle of `heap`; about one in
+/// ten key changes goes the wrong way and must be refused.
+///
+/// # Edge cases
+/// * On an empty heap, handle operations become an ExtractMax instead
+pub fn random_op(rng: &mut Rng, heap: &Heap) -> Op {
+    let roll = rng.below(100);
+    if roll < 45 {
+        return Op::Insert(rng.range_i32(-1000, 1000));
+    }
+    let target = heap
+        .handle_at(rng.below(heap.len() as u64) as usize)
+        .and_then(|handle| Ok((handle, *heap.get(handle)?)));
+    match (roll, target) {
+        (85..=89, Ok((handle, value))) => {
+            Op::IncreaseKey(handle, value.saturating_add(rng.range_i32(-10, 100)))
+        }
+        (90..=94, Ok((handle, value))) => {
+            Op::DecreaseKey(handle, value.saturating_sub(rng.range_i32(-10, 100)))
+        }
+        (95..=98, Ok((handle, _))) => Op::Delete(handle),
+        (99, _) => Op::ChangeD(rng.range_i32(2, 8) as u32),
+        _ => Op::ExtractMax,
+    }
+}
+
+/// A heap under test together with a reference model that has seen the
+/// same operations.
+pub struct TrackedHeap {
+    heap: Heap,
+    model: Model,
+}
+
+impl TrackedHeap {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            heap: Heap::bounded(2, HEAP_MAX_SIZE, &[], MaxOrder).map_err(|e| e.to_string())?,
+            model: Model::new(),
+        })
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /// Applies the operation to the heap and the model and checks they agree
+    /// on the returned value and the size.
+    ///
+    /// # Edge cases
+    /// * HeapFull on insert, EmptyHeap on extract and InvalidKeyChange on a
+    ///   wrong-way key change are expected, not failures, as long as the
+    ///   model reports the same
+    pub fn apply(&mut self, op: &Op) -> Result<(), String> {
+        model::check_op(&mut self.heap, &mut self.model, op)
+    }
+
+    /// Checks the max-heap property over the whole heap.
+    pub fn check_invariant(&self) -> Result<(), String> {
+        if self.heap.is_heap() {
+            Ok(())
+        } else {
+            Err(format!("heap property violated (d={})", self.heap.d()))
+        }
+    }
+}
+
+/// Parses `--seed N`, `--ops N` and `--check-every N` from the arguments.
+///
+/// # Edge cases
+/// * Without `--seed`, a seed is derived from the current time and printed
+/// * `--check-every 0` is rejected, since no check would ever run
+fn parse_options(args: &[String]) -> Result<StressOptions, String> {
+    let mut options = StressOptions {
+        seed: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_nanos() as u64)
+            .unwrap_or(0),
+        ops: DEFAULT_OPS,
+        check_every: DEFAULT_CHECK_EVERY,
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let value: u64 = value
+            .parse()
+            .map_err(|_| format!("Invalid value for {}: {}", flag, value))?;
+        match flag.as_str() {
+            "--seed" => options.seed = value,
+            "--ops" => options.ops = value,
+            "--check-every" if value > 0 => options.check_every = value,
+            "--check-every" => return Err("--check-every must be at least 1".to_string()),
+            _ => return Err(format!("Unknown option: {}", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Prints everything needed to reproduce a failed run.
+fn report_failure(options: &StressOptions, step: u64, op: &Op, reason: &str, heap: &Heap) {
+    eprintln!("Stress run failed!");
+    eprintln!("  seed:   {}", options.seed);
+    eprintln!("  step:   {} of {}", step, options.ops);
+    eprintln!("  op:     {:?}", op);
+    eprintln!("  reason: {}", reason);
+    eprintln!(
+        "  reproduce with: stress --seed {} --ops {} --check-every {}",
+        options.seed, options.ops, options.check_every
+    );
+    heap.print();
+}
+
+/// Runs a random mix of inserts, extractions, key changes, deletes and D
+/// changes, validating the heap invariant every `--check-every` operations.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let mut rng = Rng::new(options.seed);
+    let mut tracked = TrackedHeap::new()?;
+
+    println!(
+        "Stress: seed={} ops={} check-every={}",
+        options.seed, options.ops, options.check_every
+    );
+
+    for step in 1..=options.ops {
+        let op = random_op(&mut rng, tracked.heap());
+        let outcome = tracked.apply(&op).and_then(|()| {
+            if step % options.check_every == 0 || step == options.ops {
+                tracked.check_invariant()
+            } else {
+                Ok(())
+            }
+        });
+
+        if let Err(reason) = outcome {
+            report_failure(&options, step, &op, &reason, tracked.heap());
+            return Err(format!("stress run failed at step {}", step));
+        }
+    }
+
+    println!(
+        "Stress run passed: {} operations, final size {}",
+        options.ops,
+        tracked.heap().len()
+    );
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A point in time that the u64-keyed event queues can order by.
+///
+/// Ticks are nanoseconds since the Unix epoch, shifted up by 2^63 so
+/// instants before 1970 still sort below later ones. Converting preserves
+/// order: `a < b` exactly when `a.to_ticks() < b.to_ticks()`, whatever
+/// UTC offset either was written with. A Heap needs none of this, since
+/// `Heap<DateTime<Utc>, MinOrder>` orders by `Ord` directly.
+pub trait Timestamp: Sized {
+    /// Returns the instant as ticks.
+    ///
+    /// # Edge cases
+    /// * Instants outside about 1677 to 2262, the i64 nanosecond range,
+    ///   saturate to 0 or u64::MAX
+    fn to_ticks(&self) -> u64;
+
+    /// Returns the instant `ticks` stands for, in UTC.
+    fn from_ticks(ticks: u64) -> Self;
+}
+
+/// Added to signed nanoseconds to map them onto u64 in order.
+const EPOCH_TICKS: u64 = 1 << 63;
+
+fn ticks_from_nanos(nanos: i128) -> u64 {
+    let nanos = nanos.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+    (nanos as u64) ^ EPOCH_TICKS
+}
+
+fn nanos_from_ticks(ticks: u64) -> i64 {
+    (ticks ^ EPOCH_TICKS) as i64
+}
+
+/// Plain ticks, for callers that keep their own clock.
+impl Timestamp for u64 {
+    fn to_ticks(&self) -> u64 {
+        *self
+    }
+
+    fn from_ticks(ticks: u64) -> Self {
+        ticks
+    }
+}
+
+/// Wall-clock time without either feature.
+impl Timestamp for SystemTime {
+    fn to_ticks(&self) -> u64 {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => ticks_from_nanos(after.as_nanos() as i128),
+            Err(before) => ticks_from_nanos(-(before.duration().as_nanos() as i128)),
+        }
+    }
+
+    fn from_ticks(ticks: u64) -> Self {
+        let nanos = nanos_from_ticks(ticks);
+        let offset = Duration::from_nanos(nanos.unsigned_abs());
+        if nanos < 0 {
+            UNIX_EPOCH - offset
+        } else {
+            UNIX_EPOCH + offset
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp for chrono::DateTime<chrono::Utc> {
+    fn to_ticks(&self) -> u64 {
+        match self.timestamp_nanos_opt() {
+            Some(nanos) => ticks_from_nanos(nanos as i128),
+            None if self.timestamp() < 0 => 0,
+            None => u64::MAX,
+        }
+    }
+
+    fn from_ticks(ticks: u64) -> Self {
+        chrono::DateTime::from_timestamp_nanos(nanos_from_ticks(ticks))
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn to_ticks(&self) -> u64 {
+        ticks_from_nanos(self.unix_timestamp_nanos())
+    }
+
+    fn from_ticks(ticks: u64) -> Self {
+        match time::OffsetDateTime::from_unix_timestamp_nanos(nanos_from_ticks(ticks) as i128) {
+            Ok(at) => at,
+            Err(_) => unreachable!("every i64 nanosecond count is a valid OffsetDateTime"),
+        }
+    }
+}
+
+/// Parses an RFC 3339 timestamp such as `2026-10-14T09:30:00+02:00` into
+/// ticks, with chrono if that feature is on and time otherwise.
+///
+/// # Edge cases
+/// * Returns an error naming the text if it isn't RFC 3339
+/// * Returns an error saying so if neither feature is enabled
+pub fn parse_rfc3339(text: &str) -> Result<u64, String> {
+    if cfg!(not(any(feature = "chrono", feature = "time"))) {
+        return Err(format!(
+            "cannot read timestamp {}: built without the chrono or time feature",
+            text
+        ));
+    }
+    parse_instant(text).ok_or_else(|| format!("invalid RFC 3339 timestamp {}", text))
+}
+
+#[cfg(feature = "chrono")]
+fn parse_instant(text: &str) -> Option<u64> {
+    let at = chrono::DateTime::parse_from_rfc3339(text).ok()?;
+    Some(at.with_timezone(&chrono::Utc).to_ticks())
+}
+
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+fn parse_instant(text: &str) -> Option<u64> {
+    use time::format_description::well_known::Rfc3339;
+    let at = time::OffsetDateTime::parse(text, &Rfc3339).ok()?;
+    Some(at.to_ticks())
+}
+
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+fn parse_instant(_text: &str) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticks_keep_order_across_the_epoch() {
+        let nanos = [i64::MIN as i128, -5, -1, 0, 1, 1_000, i64::MAX as i128];
+        let ticks: Vec<u64> = nanos.iter().map(|&n| ticks_from_nanos(n)).collect();
+        assert!(ticks.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(ticks_from_nanos(0), EPOCH_TICKS);
+        assert_eq!(ticks_from_nanos(i64::MIN as i128 - 1), 0);
+        assert_eq!(nanos_from_ticks(ticks_from_nanos(-5)), -5);
+        assert_eq!(u64::from_ticks(42).to_ticks(), 42);
+
+        let before = UNIX_EPOCH - Duration::from_secs(10);
+        let after = UNIX_EPOCH + Duration::from_nanos(7);
+        assert!(before.to_ticks() < UNIX_EPOCH.to_ticks());
+        assert_eq!(SystemTime::from_ticks(before.to_ticks()), before);
+        assert_eq!(SystemTime::from_ticks(after.to_ticks()), after);
+    }
+
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    #[test]
+    fn test_parse_rfc3339_orders_by_instant() {
+        // 09:30 at +02:00 is 07:30 UTC, earlier than 08:00 UTC
+        let offset = parse_rfc3339("2026-10-14T09:30:00+02:00").unwrap();
+        let utc = parse_rfc3339("2026-10-14T08:00:00Z").unwrap();
+        assert!(offset < utc);
+        assert_eq!(utc - offset, 30 * 60 * 1_000_000_000);
+        assert!(parse_rfc3339("1969-12-31T23:59:59Z").unwrap() < EPOCH_TICKS);
+        assert_eq!(
+            parse_rfc3339("tomorrow"),
+            Err("invalid RFC 3339 timestamp tomorrow".to_string())
+        );
+    }
+
+    #[cfg(not(any(feature = "chrono", feature = "time")))]
+    #[test]
+    fn test_parse_rfc3339_needs_a_feature() {
+        assert!(parse_rfc3339("2026-10-14T08:00:00Z").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_round_trip() {
+        use chrono::{DateTime, Utc};
+        let at: DateTime<Utc> = "2026-10-14T08:00:00.123456789Z".parse().unwrap();
+        assert_eq!(DateTime::<Utc>::from_ticks(at.to_ticks()), at);
+        let far: DateTime<Utc> = "9999-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(far.to_ticks(), u64::MAX);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_round_trip() {
+        use time::format_description::well_known::Rfc3339;
+        use time::OffsetDateTime;
+        let at = OffsetDateTime::parse("1900-03-01T12:00:00+05:00", &Rfc3339).unwrap();
+        let back = OffsetDateTime::from_ticks(at.to_ticks());
+        assert_eq!(back, at);
+        assert_eq!(back.offset(), time::UtcOffset::UTC);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::keyed::KeyedQueue;
+use crate::priority::MinOrder;
+
+/// Slots per wheel level, and the bits of the deadline each level indexes.
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: u32 = 4;
+
+/// How far ahead the wheels reach, in ticks (64^4, about 4.6 hours at 1 ms).
+/// Timers further out wait in the overflow heap.
+const WHEEL_RANGE: u64 = 1 << (SLOT_BITS * LEVELS);
+
+/// A hierarchical timing wheel: four levels of 64 slots, each level 64
+/// times coarser than the one below, with a heap for timers beyond the top
+/// level.
+///
+/// Scheduling is O(1) for timers within WHEEL_RANGE and O(log m) for the m
+/// timers beyond it. Every tick fires one level-0 slot, and when a level
+/// wraps, the next slot of the level above is cascaded down into finer
+/// slots. Overflow timers move into the wheels once they come within range.
+/// The overflow heap is a KeyedQueue of payloads keyed by deadline.
+pub struct TimingWheel<T> {
+    now: u64,
+    /// levels[l][s] holds the timers due in slot s of level l.
+    levels: Vec<Vec<Vec<(u64, T)>>>,
+    /// Timers scheduled at or before `now`, fired on the next advance.
+    due: Vec<(u64, T)>,
+    overflow: KeyedQueue<u64, T, MinOrder>,
+    /// Number of timers in each level.
+    level_lens: [usize; LEVELS as usize],
+}
+
+impl<T> TimingWheel<T> {
+    /// Creates an empty wheel whose clock starts at tick `now`.
+    pub fn new(now: u64) -> Self {
+        Self {
+            now,
+            levels: (0..LEVELS)
+                .map(|_| (0..SLOTS).map(|_| Vec::new()).collect())
+                .collect(),
+            due: Vec::new(),
+            overflow: KeyedQueue::with_priority(MinOrder),
+            level_lens: [0; LEVELS as usize],
+        }
+    }
+
+    /// Returns the current tick.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the number of pending timers.
+    pub fn len(&self) -> usize {
+        self.due.len() + self.level_lens.iter().sum::<usize>() + self.overflow.len()
+    }
+
+    /// Returns true if no timers are pending.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Schedules `item` to fire at tick `deadline`.
+    ///
+    /// # Edge cases
+    /// * A deadline at or before the current tick fires on the next advance
+    pub fn schedule(&mut self, deadline: u64, item: T) {
+        if deadline <= self.now {
+            self.due.push((deadline, item));
+        } else if deadline - self.now >= WHEEL_RANGE {
+            self.overflow.push(deadline, item);
+        } else {
+            self.place(deadline, item);
+        }
+    }
+
+    /// Puts a timer due within WHEEL_RANGE into the finest level whose span
+    /// covers its distance from now.
+    fn place(&mut self, deadline: u64, item: T) {
+        let delta = deadline - self.now;
+        let level = (1..LEVELS)
+            .find(|&l| delta < 1 << (SLOT_BITS * (l + 1)) && delta >= 1 << (SLOT_BITS * l))
+            .unwrap_or(0);
+        let slot = ((deadline >> (SLOT_BITS * level)) as usize) & (SLOTS - 1);
+        self.levels[level as usize][slot].push((deadline, item));
+        self.level_lens[level as usize] += 1;
+    }
+
+    /// Moves the clock forward to tick `to` and returns every timer that fired
+    /// on the way, in deadline order.
+    ///
+    /// # Edge cases
+    /// * Timers sharing a deadline come out in no particular order
+    /// * The clock never moves backwards; an earlier `to` only fires due timers
+    pub fn advance(&mut self, to: u64) -> Vec<(u64, T)> {
+        let mut fired = std::mem::take(&mut self.due);
+        fired.sort_by_key(|&(deadline, _)| deadline);
+
+        while self.now < to {
+            self.skip_idle_ticks(to);
+            self.tick(&mut fired);
+        }
+        fired
+    }
+
+    /// Moves the clock to just before the next tick that can do any work: the
+    /// next wrap of the finest non-empty level, or the earliest overflow timer
+    /// coming in range. Slots are indexed by absolute deadline, so skipping
+    /// ticks whose slots are all empty changes nothing.
+    fn skip_idle_ticks(&mut self, to: u64) {
+        let mut target = to;
+        if let Some(level) = (0..LEVELS).find(|&l| self.level_lens[l as usize] > 0) {
+            if level == 0 {
+                return;
+            }
+            let span = 1u64 << (SLOT_BITS * level);
+            target = target.min((self.now / span + 1) * span);
+        }
+        if let Some((&deadline, _)) = self.overflow.peek() {
+            target = target.min(deadline - (WHEEL_RANGE - 1));
+        }
+        self.now = self.now.max(target - 1);
+    }
+
+    /// Advances one tick: pulls in overflow timers now in range, cascades the
+    /// levels that wrapped, then fires the level-0 slot.
+    fn tick(&mut self, fired: &mut Vec<(u64, T)>) {
+        self.now += 1;
+
+        while let Some((&deadline, _)) = self.overflow.peek() {
+            if deadline - self.now >= WHEEL_RANGE {
+                break;
+            }
+            if let Some((deadline, item)) = self.overflow.pop() {
+                self.place(deadline, item);
+            }
+        }
+
+        for level in (1..LEVELS).rev() {
+            if self.now & ((1 << (SLOT_BITS * level)) - 1) == 0 {
+                let slot = ((self.now >> (SLOT_BITS * level)) as usize) & (SLOTS - 1);
+                let timers = std::mem::take(&mut self.levels[level as usize][slot]);
+                self.level_lens[level as usize] -= timers.len();
+                for (deadline, item) in timers {
+                    self.place(deadline, item);
+                }
+            }
+        }
+
+        let slot = (self.now as usize) & (SLOTS - 1);
+        let timers = std::mem::take(&mut self.levels[0][slot]);
+        self.level_lens[0] -= timers.len();
+        fired.extend(timers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_fires_at_deadlines() {
+        let mut rng = Rng::new(21);
+        let mut wheel = TimingWheel::new(1000);
+        let mut deadlines: Vec<u64> = (0..5000)
+            .map(|_| 1000 + rng.below(3 * WHEEL_RANGE))
+            .collect();
+        for (i, &deadline) in deadlines.iter().enumerate() {
+            wheel.schedule(deadline, i);
+        }
+        assert_eq!(wheel.len(), 5000);
+
+        let mut fired = Vec::new();
+        let mut now = 1000;
+        while !wheel.is_empty() {
+            let previous = now;
+            now += 1 + rng.below(WHEEL_RANGE / 8);
+            for (deadline, i) in wheel.advance(now) {
+                // Neither early nor held back past the advance it was due in
+                assert!(deadline <= now);
+                assert!(deadline > previous || deadline == 1000);
+                assert_eq!(deadlines[i], deadline);
+                fired.push(deadline);
+            }
+        }
+
+        deadlines.sort_unstable();
+        assert_eq!(fired, deadlines);
+    }
+
+    #[test]
+    fn test_tick_by_tick() {
+        let mut wheel = TimingWheel::new(0);
+        for deadline in [1, 63, 64, 65, 4095, 4096, 300_000] {
+            wheel.schedule(deadline, deadline);
+        }
+        wheel.schedule(0, 0);
+
+        let mut fired = Vec::new();
+        for now in 0..=300_000 {
+            for (deadline, item) in wheel.advance(now) {
+                assert_eq!(deadline, item);
+                assert_eq!(deadline, now);
+                fired.push(deadline);
+            }
+        }
+        assert_eq!(fired, vec![0, 1, 63, 64, 65, 4095, 4096, 300_000]);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::heap::Heap;
+use crate::priority::Priority;
+use std::cell::OnceCell;
+use std::ops::Deref;
+
+/// A heap together with a cached copy of its k highest-priority elements.
+///
+/// The first call to `top` after a change walks sorted_iter for k elements,
+/// and later calls return the cached slice until the heap is next borrowed
+/// mutably. The cache is dropped on every `heap_mut`, whether or not the
+/// caller actually changes anything.
+pub struct TopKView<P: Priority> {
+    heap: Heap<i32, P>,
+    k: usize,
+    cache: OnceCell<Vec<i32>>,
+}
+
+impl<P: Priority> TopKView<P> {
+    /// Wraps the heap, caching up to `k` elements.
+    pub fn new(heap: Heap<i32, P>, k: usize) -> Self {
+        Self {
+            heap,
+            k,
+            cache: OnceCell::new(),
+        }
+    }
+
+    /// Returns the k highest-priority elements in extraction order, or all of
+    /// them if the heap holds fewer than k.
+    pub fn top(&self) -> &[i32] {
+        self.cache
+            .get_or_init(|| self.heap.sorted_iter().take(self.k).copied().collect())
+    }
+
+    /// Returns true if the next `top` call will be served from the cache.
+    pub fn is_cached(&self) -> bool {
+        self.cache.get().is_some()
+    }
+
+    /// Returns the heap for modification and drops the cached top k.
+    pub fn heap_mut(&mut self) -> &mut Heap<i32, P> {
+        self.cache.take();
+        &mut self.heap
+    }
+
+    /// Unwraps the heap.
+    pub fn into_heap(self) -> Heap<i32, P> {
+        self.heap
+    }
+}
+
+impl<P: Priority> Deref for TopKView<P> {
+    type Target = Heap<i32, P>;
+
+    fn deref(&self) -> &Heap<i32, P> {
+        &self.heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_invalidated_by_mutation() {
+        let mut view = TopKView::new(Heap::new(4, &[5, 12, 3, 9, 7, 1]).unwrap(), 3);
+        assert!(!view.is_cached());
+        assert_eq!(view.top(), &[12, 9, 7]);
+        assert!(view.is_cached());
+        assert_eq!(view.peek_max(), Ok(&12));
+        assert!(view.is_cached());
+
+        view.heap_mut().insert(10).unwrap();
+        assert!(!view.is_cached());
+        assert_eq!(view.top(), &[12, 10, 9]);
+
+        view.heap_mut().extract_max().unwrap();
+        assert_eq!(view.top(), &[10, 9, 7]);
+    }
+
+    #[test]
+    fn test_fewer_than_k() {
+        let view = TopKView::new(Heap::new(2, &[2, 1]).unwrap(), 10);
+        assert_eq!(view.top(), &[2, 1]);
+        assert_eq!(view.into_heap().len(), 2);
+    }
+}
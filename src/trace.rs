@@ -0,0 +1,144 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+
+/// One menu operation as written to the trace file.
+pub struct TraceRecord<'a> {
+    /// Name of the operation, such as `insert` or `extract_max`.
+    pub op: &'a str,
+    /// Numbers the user typed for the operation.
+    pub input: &'a [i64],
+    /// D of the heap after the operation.
+    pub d: u32,
+    /// Backing array after the operation.
+    pub array: &'a [i32],
+    /// Slots the element moved through, for inserts and extractions.
+    pub sift_path: Option<&'a [usize]>,
+    /// The error message if the operation failed.
+    pub error: Option<&'a str>,
+}
+
+/// Appends every menu operation to a file as one JSON object per line, so a
+/// session can be graded or replayed from the file afterwards.
+pub struct Trace {
+    file: File,
+}
+
+impl Trace {
+    /// Opens the trace file for appending, creating it if needed.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Writes a record as a single line.
+    ///
+    /// # Edge cases
+    /// * Returns the Io error if the write fails; a failure partway through
+    ///   can leave a truncated line that the next record runs on from
+    /// * Sessions appending to the same file don't interleave within a line
+    ///   as long as each line goes out in one append write, which local
+    ///   filesystems do for short lines; network filesystems may not
+    pub fn record(&self, record: &TraceRecord) -> io::Result<()> {
+        let line = format!("{}\n", to_json(record));
+        (&self.file).write_all(line.as_bytes())
+    }
+}
+
+/// Formats a record as a JSON object with fields op, input, d, array,
+/// sift_path and error, where the last two are null when absent.
+fn to_json(record: &TraceRecord) -> String {
+    let sift_path = match record.sift_path {
+        Some(path) => json_array(path),
+        None => "null".to_string(),
+    };
+    let error = match record.error {
+        Some(message) => json_string(message),
+        None => "null".to_string(),
+    };
+    format!(
+        "{{\"op\":{},\"input\":{},\"d\":{},\"array\":{},\"sift_path\":{},\"error\":{}}}",
+        json_string(record.op),
+        json_array(record.input),
+        record.d,
+        json_array(record.array),
+        sift_path,
+        error
+    )
+}
+
+fn json_array<T: ToString>(values: &[T]) -> String {
+    let items: Vec<String> = values.iter().map(T::to_string).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Quotes a string, escaping quotes, backslashes and control characters.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_format() {
+        let record = TraceRecord {
+            op: "insert",
+            input: &[7],
+            d: 2,
+            array: &[7, 3, 5],
+            sift_path: Some(&[2, 0]),
+            error: None,
+        };
+        assert_eq!(
+            to_json(&record),
+            "{\"op\":\"insert\",\"input\":[7],\"d\":2,\"array\":[7,3,5],\"sift_path\":[2,0],\"error\":null}"
+        );
+
+        let failed = TraceRecord {
+            op: "extract_max",
+            input: &[],
+            d: 3,
+            array: &[],
+            sift_path: None,
+            error: Some("say \"empty\"\n"),
+        };
+        assert!(to_json(&failed)
+            .ends_with("\"array\":[],\"sift_path\":null,\"error\":\"say \\\"empty\\\"\\n\"}"));
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn test_appends_lines() {
+        let path = std::env::temp_dir().join(format!("trace-test-{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let record = TraceRecord {
+            op: "change_d",
+            input: &[4],
+            d: 4,
+            array: &[1],
+            sift_path: None,
+            error: None,
+        };
+        Trace::open(path).unwrap().record(&record).unwrap();
+        Trace::open(path).unwrap().record(&record).unwrap();
+
+        let text = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(text.lines().count(), 2);
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs `d-heap batch` with the script piped to stdin.
+fn run_piped(script: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_d-heap"))
+        .arg("batch")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_script_file() {
+    let path = std::env::temp_dir().join(format!("d-heap-batch-{}.txt", std::process::id()));
+    fs::write(
+        &path,
+        "# build, then exercise every command\n\
+         build d=3 5 2 9 1\n\
+         insert 42\n\
+         extract-max\n\
+         change-d 2\n\
+         print\n\
+         sort\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_d-heap"))
+        .args(["--batch", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "build d=3 size=4 array=9,2,5,1\n\
+         insert value=42 size=5 array=42,9,5,1,2\n\
+         extract-max value=42 size=4 array=9,2,5,1\n\
+         change-d d=2 size=4 array=9,2,5,1\n\
+         print d=2 size=4 array=9,2,5,1\n\
+         sort values=9,5,2,1\n"
+    );
+}
+
+#[test]
+fn test_piped_script_is_deterministic() {
+    let script = "build d=4 3 1 4 1 5 9 2 6\nextract-max\nextract-max\nsort\n";
+    let first = run_piped(script);
+    assert!(first.status.success());
+    assert_eq!(stdout(&first), stdout(&run_piped(script)));
+    assert!(stdout(&first).ends_with("sort values=5,4,3,2,1,1\n"));
+}
+
+#[test]
+fn test_stops_at_first_invalid_command() {
+    let output = run_piped("build d=2 7 3\ninsert seven\ninsert 8\n");
+    assert!(!output.status.success());
+    assert_eq!(stdout(&output), "build d=2 size=2 array=7,3\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "line 2: insert: invalid number seven\n"
+    );
+
+    let output = run_piped("extract-max\n");
+    assert!(!output.status.success());
+    assert!(stdout(&output).is_empty());
+}